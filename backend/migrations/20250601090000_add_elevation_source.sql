@@ -0,0 +1,2 @@
+-- Track whether race elevations come from GPS, a DEM, or a blend of both
+ALTER TABLE races ADD COLUMN elevation_source TEXT NOT NULL DEFAULT 'gps';
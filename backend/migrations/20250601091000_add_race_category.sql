@@ -0,0 +1,17 @@
+ALTER TABLE races ADD COLUMN category TEXT NOT NULL DEFAULT 'XXS';
+ALTER TABLE races ADD COLUMN mountain_level INTEGER NOT NULL DEFAULT 0;
+
+UPDATE races SET category = CASE
+    WHEN itra_effort_distance >= 210 THEN 'XXL'
+    WHEN itra_effort_distance >= 155 THEN 'XL'
+    WHEN itra_effort_distance >= 115 THEN 'L'
+    WHEN itra_effort_distance >= 75 THEN 'M'
+    WHEN itra_effort_distance >= 45 THEN 'S'
+    WHEN itra_effort_distance >= 25 THEN 'XS'
+    ELSE 'XXS'
+END;
+
+UPDATE races SET mountain_level = MIN(12, CAST(elevation_gain_m / distance_km / 10 AS INTEGER))
+WHERE distance_km > 0;
+
+CREATE INDEX idx_races_category ON races(user_id, category);
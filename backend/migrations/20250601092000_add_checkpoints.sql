@@ -0,0 +1,13 @@
+-- Checkpoints with organiser cut-off times
+CREATE TABLE checkpoints (
+    id TEXT PRIMARY KEY NOT NULL,
+    race_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    distance_km REAL NOT NULL,
+    cutoff_time TEXT, -- HH:MM clock time
+    cutoff_day INTEGER NOT NULL DEFAULT 0, -- days after the start day
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (race_id) REFERENCES races(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_checkpoints_race_id ON checkpoints(race_id);
@@ -0,0 +1,14 @@
+-- Aid stations, stored at their position snapped onto the race track
+CREATE TABLE aid_stations (
+    id TEXT PRIMARY KEY NOT NULL,
+    race_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    distance_km REAL NOT NULL,
+    lat REAL NOT NULL,
+    lon REAL NOT NULL,
+    services TEXT NOT NULL DEFAULT '[]', -- JSON array
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (race_id) REFERENCES races(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_aid_stations_race_id ON aid_stations(race_id);
@@ -0,0 +1 @@
+ALTER TABLE races ADD COLUMN sport TEXT NOT NULL DEFAULT 'trail_run';
@@ -0,0 +1,5 @@
+-- Terrain class from the sport's processed profile; NULL until the race is
+-- classified, which the startup backfill does for existing races
+ALTER TABLE races ADD COLUMN terrain TEXT;
+
+CREATE INDEX idx_races_terrain ON races(user_id, terrain);
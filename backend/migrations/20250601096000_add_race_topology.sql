@@ -0,0 +1,3 @@
+-- Course shape from the geometry; NULL until the race is analysed
+ALTER TABLE races ADD COLUMN topology TEXT;
+ALTER TABLE races ADD COLUMN laps INTEGER NOT NULL DEFAULT 1;
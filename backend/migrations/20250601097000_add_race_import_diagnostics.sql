@@ -0,0 +1,2 @@
+-- What the GPX import found and fixed, as JSON; NULL for races not uploaded from a file
+ALTER TABLE races ADD COLUMN import_diagnostics TEXT;
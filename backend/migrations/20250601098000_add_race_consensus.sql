@@ -0,0 +1,2 @@
+-- Source races and per-km spread for consensus courses, as JSON; NULL for other races
+ALTER TABLE races ADD COLUMN consensus TEXT;
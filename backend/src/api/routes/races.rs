@@ -1,39 +1,407 @@
 use axum::{
     extract::{Extension, Path, Query, State, Multipart},
     middleware,
-    routing::get,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
     Json,
     Router,
 };
 use serde::Deserialize;
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::sync::Arc;
 use uuid::Uuid;
 use axum::http::StatusCode;
 
 use crate::api::middleware::auth::auth_middleware;
 use crate::config::settings::Settings;
-use crate::core::models::race::{Race, ElevationProfile, GradientDistribution};
-use crate::core::services::gpx_parser::parse_gpx;
+use crate::core::algorithms::altitude_exposure::{
+    altitude_exposure, AltitudeExposure, DEFAULT_BAND_EDGES_M, DEFAULT_FLAT_PACE_S_PER_KM, DEFAULT_THRESHOLD_M,
+};
+use crate::core::algorithms::consensus_track::consensus_track;
+use crate::core::algorithms::climb_detection::{detect_climbs, Climb, ClimbDetectionConfig, ClimbScale};
+use crate::core::algorithms::distance_markers::{distance_markers, markers_to_geojson, markers_to_gpx};
+use crate::core::algorithms::gradient_analysis::GradientBins;
+use crate::core::algorithms::resampling::{interpolate, resample_profile, slice_profile};
+use crate::core::algorithms::smoothing::SmoothingMethod;
+use crate::core::algorithms::splits::{split_table, splits_to_csv, SplitUnit};
+use crate::core::algorithms::steepest_sections::{find_steepest_sections, SteepestSections, DEFAULT_WINDOWS_M};
+use crate::core::algorithms::route_topology::{detect_topology, TopologyAnalysis};
+use crate::core::algorithms::terrain_classification::{classify_terrain, TerrainClass, TerrainClassification};
+use crate::core::algorithms::track_repair::TrackRepairOptions;
+use crate::core::algorithms::linear_referencing::{
+    cumulative_distances, locate_on_track, point_at, snap_to_track, LocatedPoint, TrackPosition,
+};
+use crate::core::models::aid_station::{AidStation, AidStationInput, Leg};
+use crate::core::models::pacing::{Checkpoint, CheckpointInput, DaylightPlan};
+use crate::core::models::processing::ProcessingOptions;
+use crate::core::models::sport::Sport;
+use crate::core::models::race::{
+    Race, ElevationProfile, GradientDistribution, GpxData,
+    ElevationCorrectionMode, ElevationCorrectionResult, ElevationSource,
+};
+use crate::core::services::dem_service::{DemCorrection, DemService};
+use crate::core::services::gpx_parser::{GpxStreamParser, ImportDiagnostics};
+use crate::core::services::elevation_processor::ElevationData;
 use crate::core::services::elevation_service::{
     calculate_elevation_metrics, 
     calculate_gradient_distribution
 };
-use crate::core::services::itra_calculator::calculate_itra_effort;
+use crate::core::services::cycling_power::{estimate_power, PowerEstimate, PowerTarget, RiderSetup};
+use crate::core::services::effort_model::EffortModelKind;
+use crate::core::services::leg_service::build_legs;
+use crate::core::services::route_editing;
+use crate::core::services::sport_profiles::{SportProfile, SportProfileRegistry};
+use crate::core::services::daylight_plan::{build_daylight_plan, MAX_TARGET_SECONDS};
+use crate::core::services::pacing_plan::{build_pacing_plan, pacing_plan_to_csv, parse_clock_time, parse_duration};
+use crate::core::services::pace_predictor::{flat_pace_from_activities, predict, PacePrediction};
+use crate::core::services::itra_calculator::{calculate_itra_effort, calculate_mountain_level, RaceCategory};
 use crate::errors::handlers::ApiError;
 
 #[derive(Debug, Deserialize)]
-pub struct WindowSizeQuery {
+pub struct ElevationCorrectionRequest {
+    mode: String,
+    dem_weight: Option<f64>,
+}
+
+/// Furthest an aid station given by coordinates may be from the track
+const MAX_SNAP_OFFSET_M: f64 = 500.0;
+
+/// Default radius within which a located coordinate counts as on the course
+const DEFAULT_LOCATE_RADIUS_M: f64 = 100.0;
+
+/// Most points a resampled profile or a set of distance markers may return
+const MAX_PROFILE_SAMPLES: f64 = 50_000.0;
+
+#[derive(Debug, Deserialize)]
+pub struct ResampleQuery {
+    spacing_m: Option<f64>,
+    points: Option<usize>,
+    from_km: Option<f64>,
+    to_km: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PositionQuery {
+    km: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LocateQuery {
+    lat: f64,
+    lon: f64,
+    /// Furthest from the track a pass may be, in metres
+    max_offset_m: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClimbQuery {
+    scale: Option<ClimbScale>,
+    min_gain_m: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GradientBinQuery {
+    /// Comma-separated lower bin edges in percent
+    bins: Option<String>,
+    /// Absolute gradient in percent up to which a segment is flat
+    flat: Option<f64>,
+}
+
+impl GradientBinQuery {
+    fn bins(&self, profile: &SportProfile) -> Result<GradientBins, ApiError> {
+        let defaults = profile.gradient_bins.clone();
+        let edges = match &self.bins {
+            Some(bins) => bins.split(',')
+                .map(|edge| edge.trim().parse::<f64>()
+                    .map_err(|_| ApiError::BadRequest(format!("Invalid gradient bin edge: {}", edge))))
+                .collect::<Result<Vec<f64>, ApiError>>()?,
+            None => defaults.edges,
+        };
+        
+        GradientBins::new(edges, self.flat.unwrap_or(defaults.flat_threshold))
+            .map_err(ApiError::BadRequest)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AltitudeQuery {
+    /// Comma-separated edges between altitude bands in metres
+    bands: Option<String>,
+    threshold_m: Option<f64>,
+    /// Flat pace for time estimates, in seconds per km
+    flat_pace_s_per_km: Option<f64>,
+}
+
+impl AltitudeQuery {
+    fn band_edges(&self) -> Result<Vec<f64>, ApiError> {
+        let Some(bands) = &self.bands else {
+            return Ok(DEFAULT_BAND_EDGES_M.to_vec());
+        };
+
+        bands.split(',')
+            .map(|edge| edge.trim().parse::<f64>()
+                .map_err(|_| ApiError::BadRequest(format!("Invalid altitude band edge: {}", edge))))
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PredictionRequest {
+    /// Target pace on the flat, in seconds per km
+    flat_pace_s_per_km: Option<f64>,
+    /// Past races from the library with the time they took
+    activities: Option<Vec<PastActivity>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PastActivity {
+    race_id: String,
+    elapsed_seconds: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PowerRequest {
+    target_speed_kmh: Option<f64>,
+    target_power_w: Option<f64>,
+    rider_mass_kg: Option<f64>,
+    bike_mass_kg: Option<f64>,
+    cda_m2: Option<f64>,
+    crr: Option<f64>,
+    drivetrain_loss: Option<f64>,
+}
+
+impl PowerRequest {
+    fn target(&self) -> Result<PowerTarget, ApiError> {
+        match (self.target_speed_kmh, self.target_power_w) {
+            (Some(speed), None) if speed > 0.0 => Ok(PowerTarget::SpeedKmh(speed)),
+            (None, Some(power)) if power > 0.0 => Ok(PowerTarget::PowerW(power)),
+            _ => Err(ApiError::BadRequest(
+                "Provide one positive target_speed_kmh or target_power_w".to_string(),
+            )),
+        }
+    }
+    
+    fn setup(&self) -> Result<RiderSetup, ApiError> {
+        let defaults = RiderSetup::default();
+        let setup = RiderSetup {
+            rider_mass_kg: self.rider_mass_kg.unwrap_or(defaults.rider_mass_kg),
+            bike_mass_kg: self.bike_mass_kg.unwrap_or(defaults.bike_mass_kg),
+            cda_m2: self.cda_m2.unwrap_or(defaults.cda_m2),
+            crr: self.crr.unwrap_or(defaults.crr),
+            drivetrain_loss: self.drivetrain_loss.unwrap_or(defaults.drivetrain_loss),
+        };
+        
+        if setup.rider_mass_kg + setup.bike_mass_kg <= 0.0 || setup.cda_m2 <= 0.0 || setup.crr < 0.0 {
+            return Err(ApiError::BadRequest("Masses and cda_m2 must be positive".to_string()));
+        }
+        if !(0.0..0.5).contains(&setup.drivetrain_loss) {
+            return Err(ApiError::BadRequest("drivetrain_loss must be between 0 and 0.5".to_string()));
+        }
+        
+        Ok(setup)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PacingPlanQuery {
+    /// Start clock time, e.g. `06:00`
+    start_time: String,
+    /// Target finish as a duration, e.g. `10:30` or `26:15:00`
+    target_time: String,
+    /// `json` (default) or `csv`
+    format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DaylightQuery {
+    /// Start date and time with its UTC offset, e.g. `2025-08-29T18:00:00+02:00`
+    start: String,
+    /// Target finish as a duration, e.g. `10:30` or `26:15:00`
+    target_time: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RaceListQuery {
+    /// UTMB/ITRA category band, e.g. `M`
+    category: Option<String>,
+    /// e.g. `road_bike`
+    sport: Option<Sport>,
+    terrain: Option<TerrainClass>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrimRequest {
+    from_km: f64,
+    to_km: f64,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReverseRequest {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SplitRequest {
+    at_km: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JoinRequest {
+    /// Races to run one after another, in order
+    race_ids: Vec<String>,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsensusRequest {
+    /// Recordings of the same course
+    race_ids: Vec<String>,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRaceRequest {
+    name: Option<String>,
+    sport: Option<Sport>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EffortQuery {
+    effort_model: Option<EffortModelKind>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SplitQuery {
+    unit: Option<SplitUnit>,
+    /// `json` (default) or `csv`
+    format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarkerQuery {
+    /// Distance between markers in `unit`s
+    interval: Option<f64>,
+    unit: Option<SplitUnit>,
+    /// `json` (default), `gpx` or `geojson`
+    format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SteepestQuery {
+    /// Comma-separated window lengths in metres
+    windows: Option<String>,
+}
+
+impl SteepestQuery {
+    fn windows(&self) -> Result<Vec<f64>, ApiError> {
+        let Some(windows) = &self.windows else {
+            return Ok(DEFAULT_WINDOWS_M.to_vec());
+        };
+
+        windows.split(',')
+            .map(|window| match window.trim().parse::<f64>() {
+                Ok(value) if value > 0.0 => Ok(value),
+                _ => Err(ApiError::BadRequest(format!("Invalid window length: {}", window))),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProcessingQuery {
+    /// Gradient window in metres
     window_size: Option<u32>,
     smoothed: Option<bool>,
+    algorithm: Option<String>,
+    /// Smoothing window in metres
+    smoothing_window: Option<f64>,
+    centered: Option<bool>,
+    capping: Option<bool>,
+}
+
+impl ProcessingQuery {
+    fn options(&self, profile: &SportProfile) -> Result<ProcessingOptions, ApiError> {
+        let defaults = profile.processing_options();
+        // The profile's window carries over to another algorithm unless one is given
+        let window_m = self.smoothing_window
+            .or(defaults.smoothing.window_m())
+            .unwrap_or(100.0);
+        
+        if window_m <= 0.0 {
+            return Err(ApiError::BadRequest("smoothing_window must be positive".to_string()));
+        }
+        if self.window_size == Some(0) {
+            return Err(ApiError::BadRequest("window_size must be positive".to_string()));
+        }
+        
+        let smoothing = match self.algorithm.as_deref() {
+            None => match self.smoothing_window {
+                Some(_) => SmoothingMethod::MovingAverage {
+                    window_m,
+                    centered: self.centered.unwrap_or(false),
+                },
+                None => defaults.smoothing,
+            },
+            Some("moving_average") => SmoothingMethod::MovingAverage {
+                window_m,
+                centered: self.centered.unwrap_or(false),
+            },
+            Some("savitzky_golay") => SmoothingMethod::SavitzkyGolay {
+                window_m,
+                polynomial_order: 2,
+            },
+            Some("kalman") => SmoothingMethod::Kalman {
+                measurement_noise_m: 3.0,
+                process_noise: 1e-6,
+            },
+            Some(other) => {
+                return Err(ApiError::BadRequest(format!("Unknown smoothing algorithm: {}", other)));
+            }
+        };
+        
+        Ok(ProcessingOptions {
+            smoothed: self.smoothed.unwrap_or(defaults.smoothed),
+            smoothing,
+            capping: self.capping.unwrap_or(defaults.capping),
+            gradient_window_m: self.window_size.map(|w| w as f64).unwrap_or(defaults.gradient_window_m),
+            ..defaults
+        })
+    }
 }
 
 pub fn routes(db_pool: SqlitePool, settings: Settings) -> Router {
     Router::new()
         .route("/", get(get_races).post(upload_gpx))
-        .route("/:id", get(get_race).delete(delete_race))
+        .route("/join", post(join_races))
+        .route("/consensus", post(create_consensus_race))
+        .route("/:id", get(get_race).patch(update_race).delete(delete_race))
         .route("/:id/elevation", get(get_elevation_profile))
         .route("/:id/gradient", get(get_gradient_distribution))
+        .route("/:id/splits", get(get_splits))
+        .route("/:id/markers", get(get_distance_markers))
         .route("/:id/metrics", get(get_race_metrics))
+        .route("/:id/climbs", get(get_climbs))
+        .route("/:id/steepest", get(get_steepest_sections))
+        .route("/:id/terrain", get(get_terrain))
+        .route("/:id/import-diagnostics", get(get_import_diagnostics))
+        .route("/:id/consensus", get(get_consensus))
+        .route("/:id/altitude", get(get_altitude_exposure))
+        .route("/:id/topology", get(get_topology))
+        .route("/:id/position", get(get_position))
+        .route("/:id/locate", get(locate_position))
+        .route("/:id/elevation-correction", post(correct_elevation))
+        .route("/:id/trim", post(trim_race))
+        .route("/:id/reverse", post(reverse_race))
+        .route("/:id/split", post(split_race))
+        .route("/:id/prediction", post(predict_finish_time))
+        .route("/:id/power", post(estimate_cycling_power))
+        .route("/:id/checkpoints", get(get_checkpoints).put(replace_checkpoints))
+        .route("/:id/pacing-plan", get(get_pacing_plan))
+        .route("/:id/daylight", get(get_daylight_plan))
+        .route("/:id/aid-stations", get(get_aid_stations).put(replace_aid_stations))
+        .route("/:id/legs", get(get_legs))
         .layer(middleware::from_fn_with_state(
             settings.clone(),
             auth_middleware,
@@ -43,21 +411,38 @@ pub fn routes(db_pool: SqlitePool, settings: Settings) -> Router {
 
 async fn get_races(
     Extension(user_id): Extension<String>,
+    Query(params): Query<RaceListQuery>,
     State((db_pool, _)): State<(SqlitePool, Settings)>,
 ) -> Result<Json<Vec<Race>>, ApiError> {
     println!("Getting races for user: {}", user_id);
     
+    let category = params.category.as_deref()
+        .map(|value| RaceCategory::parse(value)
+            .ok_or_else(|| ApiError::BadRequest(format!("Unknown race category: {}", value))))
+        .transpose()?
+        .map(|category| category.as_str());
+    let sport = params.sport.map(|sport| sport.as_str());
+    let terrain = params.terrain.map(|terrain| terrain.as_str());
+    
     let rows = sqlx::query!(
         r#"
         SELECT 
             id, user_id, name, gpx_data,
             distance_km, elevation_gain_m, elevation_loss_m,
-            itra_effort_distance, created_at
+            itra_effort_distance, category, mountain_level,
+            elevation_source, sport, terrain, topology, laps, created_at
         FROM races 
-        WHERE user_id = ? 
+        WHERE user_id = ? AND (? IS NULL OR category = ?) AND (? IS NULL OR sport = ?)
+            AND (? IS NULL OR terrain = ?)
         ORDER BY created_at DESC
         "#,
-        user_id
+        user_id,
+        category,
+        category,
+        sport,
+        sport,
+        terrain,
+        terrain
     )
     .fetch_all(&db_pool)
     .await?;
@@ -71,6 +456,13 @@ async fn get_races(
         elevation_gain_m: row.elevation_gain_m,
         elevation_loss_m: row.elevation_loss_m,
         itra_effort_distance: row.itra_effort_distance,
+        category: row.category,
+        mountain_level: row.mountain_level,
+        elevation_source: row.elevation_source,
+        sport: row.sport,
+        terrain: row.terrain,
+        topology: row.topology,
+        laps: row.laps,
         created_at: row.created_at,
     }).collect();
     
@@ -83,23 +475,30 @@ async fn get_race(
     Path(id): Path<String>,
     State((db_pool, _)): State<(SqlitePool, Settings)>,
 ) -> Result<Json<Race>, ApiError> {
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    
+    Ok(Json(race))
+}
+
+async fn fetch_race(db_pool: &SqlitePool, id: &str, user_id: &str) -> Result<Race, ApiError> {
     let row = sqlx::query!(
         r#"
         SELECT 
             id, user_id, name, gpx_data,
             distance_km, elevation_gain_m, elevation_loss_m,
-            itra_effort_distance, created_at
+            itra_effort_distance, category, mountain_level,
+            elevation_source, sport, terrain, topology, laps, created_at
         FROM races 
         WHERE id = ? AND user_id = ?
         "#,
         id,
         user_id
     )
-    .fetch_optional(&db_pool)
+    .fetch_optional(db_pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Race not found".to_string()))?;
     
-    let race = Race {
+    Ok(Race {
         id: row.id,
         user_id: row.user_id,
         name: row.name,
@@ -108,24 +507,35 @@ async fn get_race(
         elevation_gain_m: row.elevation_gain_m,
         elevation_loss_m: row.elevation_loss_m,
         itra_effort_distance: row.itra_effort_distance,
+        category: row.category,
+        mountain_level: row.mountain_level,
+        elevation_source: row.elevation_source,
+        sport: row.sport,
+        terrain: row.terrain,
+        topology: row.topology,
+        laps: row.laps,
         created_at: row.created_at,
-    };
-    
-    Ok(Json(race))
+    })
 }
 
 async fn upload_gpx(
     Extension(user_id): Extension<String>,
+    Extension(dem_service): Extension<Option<Arc<DemService>>>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
     State((db_pool, _)): State<(SqlitePool, Settings)>,
     mut multipart: Multipart,
 ) -> Result<Json<Race>, ApiError> {
     println!("Uploading GPX for user: {}", user_id);
     
-    let mut gpx_content = String::new();
+    let mut gpx_parser = None;
     let mut filename = String::new();
+    let mut elevation_mode = None;
+    let mut dem_weight = None;
+    let mut sport = Sport::default();
+    let mut repair = TrackRepairOptions::default();
     
     // Process multipart form
-    while let Some(field) = multipart.next_field().await
+    while let Some(mut field) = multipart.next_field().await
         .map_err(|e| {
             println!("Multipart error: {:?}", e);
             ApiError::BadRequest("Invalid multipart data".to_string())
@@ -136,238 +546,1368 @@ async fn upload_gpx(
         
         if name == "file" {
             filename = field.file_name().unwrap_or("unnamed.gpx").to_string();
-            let data = field.bytes().await
+            
+            // Parse chunks as they arrive instead of buffering the whole file
+            let mut parser = GpxStreamParser::new();
+            while let Some(chunk) = field.chunk().await
                 .map_err(|e| {
                     println!("Failed to read file: {:?}", e);
                     ApiError::BadRequest("Failed to read file".to_string())
-                })?;
+                })?
+            {
+                parser.push(&chunk)?;
+            }
             
-            gpx_content = String::from_utf8(data.to_vec())
-                .map_err(|e| {
-                    println!("Invalid UTF-8: {:?}", e);
-                    ApiError::BadRequest("Invalid UTF-8 in GPX file".to_string())
-                })?;
+            gpx_parser = Some(parser);
+        } else if name == "sport" {
+            let value = field.text().await
+                .map_err(|_| ApiError::BadRequest("Invalid sport field".to_string()))?;
+            sport = Sport::parse(&value)
+                .ok_or_else(|| ApiError::BadRequest(format!("Unknown sport: {}", value)))?;
+        } else if name == "interpolate_gaps" {
+            let value = field.text().await
+                .map_err(|_| ApiError::BadRequest("Invalid interpolate_gaps field".to_string()))?;
+            repair.interpolate_gaps = match value.trim() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return Err(ApiError::BadRequest("interpolate_gaps must be true or false".to_string())),
+            };
+        } else if name == "elevation_mode" || name == "dem_weight" {
+            let value = field.text().await
+                .map_err(|_| ApiError::BadRequest(format!("Invalid {} field", name)))?;
             
-            println!("GPX content length: {}", gpx_content.len());
+            if name == "elevation_mode" {
+                elevation_mode = Some(value);
+            } else {
+                dem_weight = Some(value.trim().parse::<f64>()
+                    .map_err(|_| ApiError::BadRequest("dem_weight must be a number".to_string()))?);
+            }
         }
     }
     
-    if gpx_content.is_empty() {
-        return Err(ApiError::BadRequest("No GPX file provided".to_string()));
-    }
-    
-    // Parse GPX file
-    let gpx_data = parse_gpx(&gpx_content)?;
+    // Parse and repair GPX file
+    let (gpx_data, diagnostics) = gpx_parser
+        .ok_or_else(|| ApiError::BadRequest("No GPX file provided".to_string()))?
+        .finish(&repair)?;
     
-    // Calculate metrics
-    let (distance_km, elevation_gain_m, elevation_loss_m) = calculate_elevation_metrics(&gpx_data);
-    let itra_effort_distance = calculate_itra_effort(distance_km, elevation_gain_m);
+    // Apply DEM elevations when requested, or when the file has none at all
+    let has_elevation = gpx_data.points.iter().any(|p| p.ele != 0.0);
+    let mut elevation_source = ElevationSource::Gps;
+    let gpx_data = match elevation_mode.as_deref() {
+        Some(mode) => match parse_correction_mode(mode, dem_weight)? {
+            Some(mode) => {
+                let dem_service = dem_service.ok_or_else(|| {
+                    ApiError::BadRequest("Elevation correction is not configured".to_string())
+                })?;
+                let correction = apply_dem_correction(dem_service, gpx_data, mode).await?;
+                elevation_source = correction.source;
+                correction.gpx_data
+            }
+            None => gpx_data,
+        },
+        None if !has_elevation && dem_service.is_some() => {
+            println!("GPX has no elevation data, filling from DEM");
+            match apply_dem_correction(dem_service.unwrap(), gpx_data.clone(), ElevationCorrectionMode::Replace).await {
+                Ok(correction) => {
+                    elevation_source = correction.source;
+                    correction.gpx_data
+                }
+                Err(_) => gpx_data,
+            }
+        }
+        None => gpx_data,
+    };
     
-    // Create race
-    let race_id = Uuid::new_v4().to_string();
     let race_name = if !filename.is_empty() && filename != "unnamed.gpx" {
         filename.replace(".gpx", "")
     } else {
         format!("Race {}", chrono::Utc::now().format("%Y-%m-%d %H:%M"))
     };
-    let gpx_json = serde_json::to_string(&gpx_data)?;
     
-    println!("Creating race: {} with distance: {}km", race_name, distance_km);
+    let mut tx = db_pool.begin().await?;
+    let race_id = insert_race(&mut tx, &user_id, &race_name, &gpx_data, sport, elevation_source, &sport_profiles).await?;
+    
+    let diagnostics_json = serde_json::to_string(&diagnostics)?;
+    sqlx::query!(
+        "UPDATE races SET import_diagnostics = ? WHERE id = ?",
+        diagnostics_json,
+        race_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    
+    let race = fetch_race(&db_pool, &race_id, &user_id).await?;
+    println!("Race created successfully: {}", race.id);
+    Ok(Json(race))
+}
+
+/// Store a new race, computing every stored metric from its track, and return its id
+async fn insert_race(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+    name: &str,
+    gpx_data: &GpxData,
+    sport: Sport,
+    elevation_source: ElevationSource,
+    sport_profiles: &SportProfileRegistry,
+) -> Result<String, ApiError> {
+    // Calculate metrics
+    let (distance_km, elevation_gain_m, elevation_loss_m) = calculate_elevation_metrics(gpx_data);
+    let itra_effort_distance = calculate_itra_effort(distance_km, elevation_gain_m);
+    let category = RaceCategory::from_effort(itra_effort_distance).as_str();
+    let mountain_level = calculate_mountain_level(distance_km, elevation_gain_m);
+    let terrain = sport_profiles.get(sport).terrain(gpx_data).as_str();
+    let topology = detect_topology(gpx_data);
+    let laps = topology.laps as i64;
+    let topology = topology.topology.as_str();
+    
+    // Create race
+    let race_id = Uuid::new_v4().to_string();
+    let gpx_json = serde_json::to_string(gpx_data)?;
+    let elevation_source = elevation_source.as_str();
+    let sport = sport.as_str();
+    
+    println!("Creating {} race: {} with distance: {}km", sport, name, distance_km);
     
     sqlx::query!(
         r#"
         INSERT INTO races (
             id, user_id, name, gpx_data,
-            distance_km, elevation_gain_m, elevation_loss_m, itra_effort_distance
+            distance_km, elevation_gain_m, elevation_loss_m, itra_effort_distance,
+            category, mountain_level, elevation_source, sport, terrain, topology, laps
         )
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         race_id,
         user_id,
-        race_name,
+        name,
         gpx_json,
         distance_km,
         elevation_gain_m,
         elevation_loss_m,
-        itra_effort_distance
+        itra_effort_distance,
+        category,
+        mountain_level,
+        elevation_source,
+        sport,
+        terrain,
+        topology,
+        laps
     )
-    .execute(&db_pool)
+    .execute(&mut *conn)
     .await?;
     
-    // Fetch and return the created race
-    let row = sqlx::query!(
-        r#"
-        SELECT 
-            id, user_id, name, gpx_data,
-            distance_km, elevation_gain_m, elevation_loss_m,
-            itra_effort_distance, created_at
-        FROM races 
-        WHERE id = ?
-        "#,
-        race_id
-    )
-    .fetch_one(&db_pool)
-    .await?;
+    Ok(race_id)
+}
+
+/// Store an edited copy of `race` as a new race with the same sport and elevation source
+async fn insert_edited_race(
+    conn: &mut SqliteConnection,
+    race: &Race,
+    name: &str,
+    gpx_data: &GpxData,
+    sport_profiles: &SportProfileRegistry,
+) -> Result<String, ApiError> {
+    let elevation_source = ElevationSource::parse(&race.elevation_source).unwrap_or(ElevationSource::Gps);
+    insert_race(conn, &race.user_id, name, gpx_data, race_sport(race), elevation_source, sport_profiles).await
+}
+
+async fn trim_race(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+    Json(payload): Json<TrimRequest>,
+) -> Result<Json<Race>, ApiError> {
+    println!("Trimming race {} to {}-{}km", id, payload.from_km, payload.to_km);
     
-    let race = Race {
-        id: row.id,
-        user_id: row.user_id,
-        name: row.name,
-        gpx_data: row.gpx_data,
-        distance_km: row.distance_km,
-        elevation_gain_m: row.elevation_gain_m,
-        elevation_loss_m: row.elevation_loss_m,
-        itra_effort_distance: row.itra_effort_distance,
-        created_at: row.created_at,
-    };
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let trimmed = route_editing::trim(&gpx_data, payload.from_km, payload.to_km)
+        .map_err(ApiError::BadRequest)?;
     
-    println!("Race created successfully: {}", race.id);
-    Ok(Json(race))
+    let name = payload.name.unwrap_or_else(|| format!("{} (trimmed)", race.name));
+    let race_id = insert_edited_race(&mut *db_pool.acquire().await?, &race, &name, &trimmed, &sport_profiles).await?;
+    Ok(Json(fetch_race(&db_pool, &race_id, &user_id).await?))
 }
 
-async fn delete_race(
+async fn reverse_race(
     Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
     Path(id): Path<String>,
     State((db_pool, _)): State<(SqlitePool, Settings)>,
-) -> Result<StatusCode, ApiError> {
-    println!("Deleting race {} for user {}", id, user_id);
-    
-    let result = sqlx::query!(
-        r#"DELETE FROM races WHERE id = ? AND user_id = ?"#,
-        id,
-        user_id
-    )
-    .execute(&db_pool)
-    .await?;
+    Json(payload): Json<ReverseRequest>,
+) -> Result<Json<Race>, ApiError> {
+    println!("Reversing race {}", id);
     
-    if result.rows_affected() == 0 {
-        return Err(ApiError::NotFound("Race not found".to_string()));
-    }
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let reversed = route_editing::reverse(&gpx_data);
     
-    println!("Race deleted successfully");
-    Ok(StatusCode::NO_CONTENT)
+    let name = payload.name.unwrap_or_else(|| format!("{} (reversed)", race.name));
+    let race_id = insert_edited_race(&mut *db_pool.acquire().await?, &race, &name, &reversed, &sport_profiles).await?;
+    Ok(Json(fetch_race(&db_pool, &race_id, &user_id).await?))
 }
 
-async fn get_elevation_profile(
+async fn split_race(
     Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
     Path(id): Path<String>,
-    Query(params): Query<WindowSizeQuery>,
     State((db_pool, _)): State<(SqlitePool, Settings)>,
-) -> Result<Json<ElevationProfile>, ApiError> {
-    println!("=== GET ELEVATION PROFILE ===");
-    println!("Race ID: {}", id);
-    println!("Window size: {:?}", params.window_size);
-    println!("Smoothed: {:?}", params.smoothed);
-    
-    // Get the race data
-    let row = sqlx::query!(
-        r#"SELECT gpx_data FROM races WHERE id = ? AND user_id = ?"#,
-        id,
-        user_id
-    )
-    .fetch_optional(&db_pool)
-    .await?
-    .ok_or_else(|| ApiError::NotFound("Race not found".to_string()))?;
+    Json(payload): Json<SplitRequest>,
+) -> Result<Json<Vec<Race>>, ApiError> {
+    println!("Splitting race {} at {}km", id, payload.at_km);
     
-    // Parse GPX data
-    let gpx_data: crate::core::models::race::GpxData = serde_json::from_str(&row.gpx_data)?;
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let (first, second) = route_editing::split(&gpx_data, payload.at_km)
+        .map_err(ApiError::BadRequest)?;
     
-    // Calculate elevation profile
-    // Use elevation processor for smoothing
-    use crate::core::services::elevation_processor::ElevationData;
+    let mut tx = db_pool.begin().await?;
+    let first = insert_edited_race(&mut tx, &race, &format!("{} (part 1)", race.name), &first, &sport_profiles).await?;
+    let second = insert_edited_race(&mut tx, &race, &format!("{} (part 2)", race.name), &second, &sport_profiles).await?;
+    tx.commit().await?;
+    Ok(Json(vec![
+        fetch_race(&db_pool, &first, &user_id).await?,
+        fetch_race(&db_pool, &second, &user_id).await?,
+    ]))
+}
+
+async fn join_races(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+    Json(payload): Json<JoinRequest>,
+) -> Result<Json<Race>, ApiError> {
+    println!("Joining races {:?}", payload.race_ids);
     
-    let smoothed = true; // Always use smoothing
-    let elevation_data = ElevationData::from_gpx_data(&gpx_data, smoothed);
+    if payload.race_ids.len() < 2 {
+        return Err(ApiError::BadRequest("Provide at least two races to join".to_string()));
+    }
     
-    // Convert to elevation profile format
-    let profile = crate::core::models::race::ElevationProfile {
-        distance: elevation_data.cumulative_distance.iter()
-            .map(|d| d / 1000.0) // Convert to km
-            .collect(),
-        elevation: elevation_data.enhanced_altitude.clone(),
-        smoothed,
-        window_size: params.window_size.unwrap_or(75), // Default 75m
-    };
+    let (races, courses) = fetch_races(&db_pool, &payload.race_ids, &user_id).await?;
+    let joined = route_editing::join(&courses);
     
-    println!("Returning profile with {} points, smoothed={}", profile.distance.len(), smoothed);
+    let elevation_source = combined_elevation_source(&races);
+    let name = payload.name.unwrap_or_else(|| {
+        races.iter().map(|race| race.name.as_str()).collect::<Vec<_>>().join(" + ")
+    });
     
-    Ok(Json(profile))
+    let race_id = insert_race(&mut *db_pool.acquire().await?, &user_id, &name, &joined, race_sport(&races[0]), elevation_source, &sport_profiles).await?;
+    Ok(Json(fetch_race(&db_pool, &race_id, &user_id).await?))
 }
 
-async fn get_gradient_distribution(
+async fn create_consensus_race(
     Extension(user_id): Extension<String>,
-    Path(id): Path<String>,
-    Query(params): Query<WindowSizeQuery>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
     State((db_pool, _)): State<(SqlitePool, Settings)>,
-) -> Result<Json<GradientDistribution>, ApiError> {
-    println!("=== GET GRADIENT DISTRIBUTION ===");
-    println!("Race ID: {}", id);
-    println!("Window size: {:?}", params.window_size);
-    println!("Smoothed: {:?}", params.smoothed);
+    Json(payload): Json<ConsensusRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    println!("=== CREATE CONSENSUS RACE ===");
+    println!("Recordings: {:?}", payload.race_ids);
     
-    // Get the race data
-    let row = sqlx::query!(
-        r#"SELECT gpx_data FROM races WHERE id = ? AND user_id = ?"#,
-        id,
-        user_id
-    )
-    .fetch_optional(&db_pool)
-    .await?
-    .ok_or_else(|| ApiError::NotFound("Race not found".to_string()))?;
+    if payload.race_ids.len() < 2 {
+        return Err(ApiError::BadRequest("Provide at least two recordings".to_string()));
+    }
     
-    // Parse GPX data
-    let gpx_data: crate::core::models::race::GpxData = serde_json::from_str(&row.gpx_data)?;
+    let (races, recordings) = fetch_races(&db_pool, &payload.race_ids, &user_id).await?;
+    let consensus = tokio::task::spawn_blocking(move || consensus_track(&recordings))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Consensus task failed: {}", e)))?
+        .map_err(ApiError::BadRequest)?;
+    println!("Consensus points: {}", consensus.gpx_data.points.len());
     
-    // Calculate gradient distribution
-    // Use a fixed 75m window for gradient calculations
-    let effective_window = 75;
-    let distribution = calculate_gradient_distribution(&gpx_data, effective_window);
+    let name = payload.name.unwrap_or_else(|| format!("{} (consensus)", races[0].name));
+    let mut tx = db_pool.begin().await?;
+    let race_id = insert_race(
+        &mut tx,
+        &user_id,
+        &name,
+        &consensus.gpx_data,
+        race_sport(&races[0]),
+        combined_elevation_source(&races),
+        &sport_profiles,
+    ).await?;
     
-    Ok(Json(distribution))
+    let summary = serde_json::json!({
+        "source_race_ids": payload.race_ids,
+        "recordings": races.len(),
+        "spread": consensus.spread,
+    });
+    let summary_json = summary.to_string();
+    sqlx::query!(
+        "UPDATE races SET consensus = ? WHERE id = ?",
+        summary_json,
+        race_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    
+    let race = fetch_race(&db_pool, &race_id, &user_id).await?;
+    println!("Consensus race created: {}", race.id);
+    Ok(Json(serde_json::json!({
+        "race": race,
+        "consensus": summary,
+    })))
 }
 
-async fn get_race_metrics(
+async fn get_consensus(
     Extension(user_id): Extension<String>,
     Path(id): Path<String>,
-    Query(params): Query<WindowSizeQuery>,
     State((db_pool, _)): State<(SqlitePool, Settings)>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
-    println!("=== GET RACE METRICS ===");
+    println!("=== GET CONSENSUS ===");
     println!("Race ID: {}", id);
-    println!("Smoothed: {:?}", params.smoothed);
-    
-    // Get the race data
+
     let row = sqlx::query!(
-        r#"SELECT gpx_data FROM races WHERE id = ? AND user_id = ?"#,
+        "SELECT consensus FROM races WHERE id = ? AND user_id = ?",
         id,
         user_id
     )
     .fetch_optional(&db_pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Race not found".to_string()))?;
+
+    let consensus = row.consensus
+        .ok_or_else(|| ApiError::NotFound("Race is not a consensus course".to_string()))?;
+    Ok(Json(serde_json::from_str(&consensus)?))
+}
+
+/// Races in the order given, with their parsed tracks
+async fn fetch_races(db_pool: &SqlitePool, ids: &[String], user_id: &str) -> Result<(Vec<Race>, Vec<GpxData>), ApiError> {
+    let mut races = Vec::with_capacity(ids.len());
+    let mut tracks = Vec::with_capacity(ids.len());
+    for id in ids {
+        let race = fetch_race(db_pool, id, user_id).await?;
+        tracks.push(serde_json::from_str::<GpxData>(&race.gpx_data)?);
+        races.push(race);
+    }
+    Ok((races, tracks))
+}
+
+/// Elevation source of a race built from several others
+fn combined_elevation_source(races: &[Race]) -> ElevationSource {
+    // Mixed sources are partly DEM, which rules out correcting them again
+    let first_source = &races[0].elevation_source;
+    if races.iter().all(|race| &race.elevation_source == first_source) {
+        ElevationSource::parse(first_source).unwrap_or(ElevationSource::Gps)
+    } else {
+        ElevationSource::Blended
+    }
+}
+
+async fn update_race(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+    Json(payload): Json<UpdateRaceRequest>,
+) -> Result<Json<Race>, ApiError> {
+    println!("Updating race {} for user {}", id, user_id);
     
-    // Parse GPX data
-    let gpx_data: crate::core::models::race::GpxData = serde_json::from_str(&row.gpx_data)?;
-    
-    // Calculate metrics with smoothing if requested
-    use crate::core::services::elevation_processor::ElevationData;
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let name = match payload.name {
+        Some(name) if name.trim().is_empty() => {
+            return Err(ApiError::BadRequest("Race name cannot be empty".to_string()));
+        }
+        Some(name) => name.trim().to_string(),
+        None => race.name.clone(),
+    };
+    let sport = payload.sport.unwrap_or_else(|| race_sport(&race));
+    // Processing follows the sport, so the terrain class may change with it
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let terrain = sport_profiles.get(sport).terrain(&gpx_data).as_str();
+    let sport = sport.as_str();
+    
+    sqlx::query!(
+        "UPDATE races SET name = ?, sport = ?, terrain = ? WHERE id = ? AND user_id = ?",
+        name,
+        sport,
+        terrain,
+        id,
+        user_id
+    )
+    .execute(&db_pool)
+    .await?;
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    Ok(Json(race))
+}
+
+async fn delete_race(
+    Extension(user_id): Extension<String>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<StatusCode, ApiError> {
+    println!("Deleting race {} for user {}", id, user_id);
+    
+    let result = sqlx::query!(
+        r#"DELETE FROM races WHERE id = ? AND user_id = ?"#,
+        id,
+        user_id
+    )
+    .execute(&db_pool)
+    .await?;
+    
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Race not found".to_string()));
+    }
+    
+    println!("Race deleted successfully");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn correct_elevation(
+    Extension(user_id): Extension<String>,
+    Extension(dem_service): Extension<Option<Arc<DemService>>>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+    Json(payload): Json<ElevationCorrectionRequest>,
+) -> Result<Json<ElevationCorrectionResult>, ApiError> {
+    println!("=== ELEVATION CORRECTION ===");
+    println!("Race ID: {}, mode: {}", id, payload.mode);
+    
+    let dem_service = dem_service
+        .ok_or_else(|| ApiError::BadRequest("Elevation correction is not configured".to_string()))?;
+    let mode = parse_correction_mode(&payload.mode, payload.dem_weight)?
+        .ok_or_else(|| ApiError::BadRequest("mode must be 'replace' or 'blend'".to_string()))?;
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    if race.elevation_source != ElevationSource::Gps.as_str() {
+        return Err(ApiError::BadRequest("Race elevations have already been corrected".to_string()));
+    }
+    
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let points_total = gpx_data.points.len();
+    let correction = apply_dem_correction(dem_service, gpx_data, mode).await?;
+    
+    // Recompute stored metrics from the corrected elevations
+    let (distance_km, elevation_gain_m, elevation_loss_m) = calculate_elevation_metrics(&correction.gpx_data);
+    let itra_effort_distance = calculate_itra_effort(distance_km, elevation_gain_m);
+    let category = RaceCategory::from_effort(itra_effort_distance).as_str();
+    let mountain_level = calculate_mountain_level(distance_km, elevation_gain_m);
+    let terrain = sport_profiles.get(race_sport(&race)).terrain(&correction.gpx_data).as_str();
+    let gpx_json = serde_json::to_string(&correction.gpx_data)?;
+    let elevation_source = correction.source.as_str();
+    
+    sqlx::query!(
+        r#"
+        UPDATE races
+        SET gpx_data = ?, distance_km = ?, elevation_gain_m = ?, elevation_loss_m = ?,
+            itra_effort_distance = ?, category = ?, mountain_level = ?, elevation_source = ?,
+            terrain = ?
+        WHERE id = ? AND user_id = ?
+        "#,
+        gpx_json,
+        distance_km,
+        elevation_gain_m,
+        elevation_loss_m,
+        itra_effort_distance,
+        category,
+        mountain_level,
+        elevation_source,
+        terrain,
+        id,
+        user_id
+    )
+    .execute(&db_pool)
+    .await?;
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    
+    Ok(Json(ElevationCorrectionResult {
+        race,
+        elevation_source: correction.source,
+        points_corrected: correction.points_corrected,
+        points_total,
+    }))
+}
+
+/// Map an `elevation_mode` value to a correction; `gps` keeps the recorded elevations
+fn parse_correction_mode(mode: &str, dem_weight: Option<f64>) -> Result<Option<ElevationCorrectionMode>, ApiError> {
+    match mode.trim() {
+        "gps" => Ok(None),
+        "replace" => Ok(Some(ElevationCorrectionMode::Replace)),
+        "blend" => {
+            let dem_weight = dem_weight.unwrap_or(0.5);
+            if !(0.0..=1.0).contains(&dem_weight) {
+                return Err(ApiError::BadRequest("dem_weight must be between 0 and 1".to_string()));
+            }
+            Ok(Some(ElevationCorrectionMode::Blend { dem_weight }))
+        }
+        other => Err(ApiError::BadRequest(format!("Unknown elevation mode: {}", other))),
+    }
+}
+
+/// Run a DEM correction off the async runtime, since tile loading reads from disk
+async fn apply_dem_correction(
+    dem_service: Arc<DemService>,
+    gpx_data: GpxData,
+    mode: ElevationCorrectionMode,
+) -> Result<DemCorrection, ApiError> {
+    tokio::task::spawn_blocking(move || dem_service.correct(&gpx_data, mode))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("DEM correction failed: {}", e)))?
+}
+
+/// A race's track along with its sport, which picks the processing profile
+async fn load_gpx_data(db_pool: &SqlitePool, id: &str, user_id: &str) -> Result<(GpxData, Sport), ApiError> {
+    let row = sqlx::query!(
+        r#"SELECT gpx_data, sport FROM races WHERE id = ? AND user_id = ?"#,
+        id,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Race not found".to_string()))?;
+    
+    Ok((serde_json::from_str(&row.gpx_data)?, Sport::parse(&row.sport).unwrap_or_default()))
+}
+
+fn race_sport(race: &Race) -> Sport {
+    Sport::parse(&race.sport).unwrap_or_default()
+}
+
+async fn get_elevation_profile(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(resample): Query<ResampleQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<ElevationProfile>, ApiError> {
+    println!("=== GET ELEVATION PROFILE ===");
+    println!("Race ID: {}", id);
+    
+    println!("Resampling: {:?}", resample);
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(sport);
+    let options = params.options(profile)?;
+    println!("Sport: {}, processing options: {:?}", sport.as_str(), options);
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let distances = &elevation_data.cumulative_distance;
+    let elevations = &elevation_data.processed_altitude();
+    
+    // Zoom range, clamped to the course
+    let total_m = distances.last().copied().unwrap_or(0.0);
+    let from_m = resample.from_km.map(|km| km * 1000.0).unwrap_or(0.0).clamp(0.0, total_m);
+    let to_m = resample.to_km.map(|km| km * 1000.0).unwrap_or(total_m).clamp(0.0, total_m);
+    if to_m <= from_m && total_m > 0.0 {
+        return Err(ApiError::BadRequest("to_km must be greater than from_km".to_string()));
+    }
+    
+    if resample.points.is_some_and(|points| points < 2) {
+        return Err(ApiError::BadRequest("points must be at least 2".to_string()));
+    }
+    let spacing_m = match (resample.spacing_m, resample.points) {
+        (Some(spacing), _) => Some(spacing),
+        (None, Some(points)) => Some((to_m - from_m) / (points - 1) as f64),
+        (None, None) => None,
+    };
+    if let Some(spacing) = spacing_m {
+        if spacing <= 0.0 || (to_m - from_m) / spacing > MAX_PROFILE_SAMPLES {
+            return Err(ApiError::BadRequest("Requested profile resolution is too fine".to_string()));
+        }
+    }
+    
+    let sampled = match spacing_m {
+        Some(spacing) => Some(resample_profile(distances, elevations, spacing, from_m, to_m)),
+        // Zoom without resampling: full detail inside the range only
+        None if resample.from_km.is_some() || resample.to_km.is_some() => {
+            Some(slice_profile(distances, elevations, from_m, to_m))
+        }
+        None => None,
+    };
+    
+    // Convert to elevation profile format
+    let profile = match sampled {
+        Some(sampled) => ElevationProfile {
+            distance: sampled.distance_m.iter().map(|d| d / 1000.0).collect(),
+            elevation: sampled.elevation,
+            smoothed: options.smoothed,
+            window_size: options.gradient_window_m as u32,
+            smoothing_applied: elevation_data.smoothing_applied,
+            options,
+            min_elevation: Some(sampled.min_elevation),
+            max_elevation: Some(sampled.max_elevation),
+            spacing_m,
+        },
+        None => ElevationProfile {
+            distance: distances.iter()
+                .map(|d| d / 1000.0) // Convert to km
+                .collect(),
+            elevation: elevations.clone(),
+            smoothed: options.smoothed,
+            window_size: options.gradient_window_m as u32,
+            smoothing_applied: elevation_data.smoothing_applied,
+            options,
+            min_elevation: None,
+            max_elevation: None,
+            spacing_m: None,
+        },
+    };
+    
+    println!("Returning profile with {} points, smoothed={}", profile.distance.len(), profile.smoothing_applied);
+    
+    Ok(Json(profile))
+}
+
+async fn get_gradient_distribution(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(bin_params): Query<GradientBinQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<GradientDistribution>, ApiError> {
+    println!("=== GET GRADIENT DISTRIBUTION ===");
+    println!("Race ID: {}", id);
+    
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(sport);
+    let options = params.options(profile)?;
+    let bins = bin_params.bins(profile)?;
+    println!("Sport: {}, processing options: {:?}", sport.as_str(), options);
+    let distribution = calculate_gradient_distribution(&gpx_data, &options, &bins);
+    
+    Ok(Json(distribution))
+}
+
+async fn get_splits(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(split_params): Query<SplitQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Response, ApiError> {
+    println!("=== GET SPLITS ===");
+    println!("Race ID: {}", id);
+    
+    let unit = split_params.unit.unwrap_or(SplitUnit::Kilometre);
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(sport);
+    let options = params.options(profile)?;
+    println!("Sport: {}, processing options: {:?}", sport.as_str(), options);
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let splits = split_table(&elevation_data, unit);
+    
+    println!("Built {} splits per {}", splits.len(), unit.as_str());
+    
+    match split_params.format.as_deref() {
+        None | Some("json") => Ok(Json(splits).into_response()),
+        Some("csv") => Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"splits-{}.csv\"", id)),
+            ],
+            splits_to_csv(&splits, unit),
+        ).into_response()),
+        Some(other) => Err(ApiError::BadRequest(format!("Unknown format: {}", other))),
+    }
+}
+
+async fn get_distance_markers(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(marker_params): Query<MarkerQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Response, ApiError> {
+    println!("=== GET DISTANCE MARKERS ===");
+    println!("Race ID: {}", id);
+    
+    let unit = marker_params.unit.unwrap_or(SplitUnit::Kilometre);
+    let interval = marker_params.interval.unwrap_or(1.0);
+    if interval <= 0.0 {
+        return Err(ApiError::BadRequest("interval must be positive".to_string()));
+    }
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(race_sport(&race));
+    let options = params.options(profile)?;
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    
+    let total = elevation_data.cumulative_distance.last().copied().unwrap_or(0.0) / unit.metres();
+    if total / interval > MAX_PROFILE_SAMPLES {
+        return Err(ApiError::BadRequest("interval is too small for this course".to_string()));
+    }
+    let markers = distance_markers(&gpx_data, &elevation_data, interval, unit);
     
-    let smoothed = true; // Always use smoothing
-    let elevation_data = ElevationData::from_gpx_data(&gpx_data, smoothed);
+    println!("Built {} markers every {} {}", markers.len(), interval, unit.as_str());
+    
+    match marker_params.format.as_deref() {
+        None | Some("json") => Ok(Json(markers).into_response()),
+        Some("gpx") => Ok((
+            [
+                (header::CONTENT_TYPE, "application/gpx+xml".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"markers-{}.gpx\"", id)),
+            ],
+            markers_to_gpx(&markers, &race.name),
+        ).into_response()),
+        Some("geojson") => Ok((
+            [
+                (header::CONTENT_TYPE, "application/geo+json".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"markers-{}.geojson\"", id)),
+            ],
+            markers_to_geojson(&markers, unit).to_string(),
+        ).into_response()),
+        Some(other) => Err(ApiError::BadRequest(format!("Unknown format: {}", other))),
+    }
+}
+
+async fn get_race_metrics(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(effort_params): Query<EffortQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    println!("=== GET RACE METRICS ===");
+    println!("Race ID: {}", id);
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(sport);
+    let options = params.options(profile)?;
+    println!("Sport: {}, processing options: {:?}", sport.as_str(), options);
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
     
     // Get the final accumulated values
     let elevation_gain = elevation_data.accumulated_ascent.last().copied().unwrap_or(0.0);
     let elevation_loss = elevation_data.accumulated_descent.last().copied().unwrap_or(0.0);
     
-    // Calculate ITRA with smoothed values
+    // Calculate ITRA with processed values
     let distance_km = elevation_data.cumulative_distance.last().copied().unwrap_or(0.0) / 1000.0;
     let itra_effort = calculate_itra_effort(distance_km, elevation_gain);
     
-    println!("Metrics - Gain: {:.1}m, Loss: {:.1}m, ITRA: {:.1}", elevation_gain, elevation_loss, itra_effort);
+    let effort = effort_params.effort_model.unwrap_or(profile.effort_model).model().estimate(&elevation_data);
+    
+    println!("Metrics - Gain: {:.1}m, Loss: {:.1}m, ITRA: {:.1}, {:?} effort: {:.1}",
+        elevation_gain, elevation_loss, itra_effort, effort.model, effort.effort_distance_km);
     
     Ok(Json(serde_json::json!({
         "elevationGainM": elevation_gain,
         "elevationLossM": elevation_loss,
-        "itraEffortDistance": itra_effort
+        "itraEffortDistance": itra_effort,
+        "category": RaceCategory::from_effort(itra_effort),
+        "enduranceLevel": RaceCategory::from_effort(itra_effort).endurance_level(),
+        "mountainLevel": calculate_mountain_level(distance_km, elevation_gain),
+        "effortModel": effort.model,
+        "effortDistance": effort.effort_distance_km,
+        "movingTimeHours": effort.moving_time_hours,
+        "smoothingApplied": elevation_data.smoothing_applied,
+        "options": options
     })))
-}
\ No newline at end of file
+}
+
+async fn get_climbs(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(climb_params): Query<ClimbQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<Vec<Climb>>, ApiError> {
+    println!("=== GET CLIMBS ===");
+    println!("Race ID: {}", id);
+    
+    let defaults = ClimbDetectionConfig::default();
+    let config = ClimbDetectionConfig {
+        min_gain_m: climb_params.min_gain_m.unwrap_or(defaults.min_gain_m),
+        scale: climb_params.scale.unwrap_or(defaults.scale),
+        ..defaults
+    };
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(sport);
+    let options = params.options(profile)?;
+    println!("Sport: {}, processing options: {:?}", sport.as_str(), options);
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let climbs = detect_climbs(&elevation_data, &config);
+    
+    println!("Found {} climbs", climbs.len());
+    
+    Ok(Json(climbs))
+}
+
+async fn get_steepest_sections(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(steepest_params): Query<SteepestQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<Vec<SteepestSections>>, ApiError> {
+    println!("=== GET STEEPEST SECTIONS ===");
+    println!("Race ID: {}", id);
+    
+    let windows = steepest_params.windows()?;
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(sport);
+    let options = params.options(profile)?;
+    println!("Sport: {}, processing options: {:?}", sport.as_str(), options);
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    
+    Ok(Json(find_steepest_sections(&gpx_data, &elevation_data, &windows)))
+}
+
+async fn get_terrain(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<TerrainClassification>, ApiError> {
+    println!("=== GET TERRAIN ===");
+    println!("Race ID: {}", id);
+
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(sport);
+    let options = params.options(profile)?;
+    println!("Sport: {}, processing options: {:?}", sport.as_str(), options);
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let classification = classify_terrain(&elevation_data);
+
+    println!("Terrain: {} ({} sections)", classification.terrain.as_str(), classification.sections.len());
+    Ok(Json(classification))
+}
+
+async fn get_import_diagnostics(
+    Extension(user_id): Extension<String>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<ImportDiagnostics>, ApiError> {
+    println!("=== GET IMPORT DIAGNOSTICS ===");
+    println!("Race ID: {}", id);
+
+    let row = sqlx::query!(
+        "SELECT import_diagnostics FROM races WHERE id = ? AND user_id = ?",
+        id,
+        user_id
+    )
+    .fetch_optional(&db_pool)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Race not found".to_string()))?;
+
+    // Edited races and races uploaded before diagnostics were kept have none
+    let diagnostics = row.import_diagnostics
+        .ok_or_else(|| ApiError::NotFound("Race has no import diagnostics".to_string()))?;
+    Ok(Json(serde_json::from_str(&diagnostics)?))
+}
+
+async fn get_altitude_exposure(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(altitude_params): Query<AltitudeQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<AltitudeExposure>, ApiError> {
+    println!("=== GET ALTITUDE EXPOSURE ===");
+    println!("Race ID: {}", id);
+    
+    let band_edges = altitude_params.band_edges()?;
+    let threshold_m = altitude_params.threshold_m.unwrap_or(DEFAULT_THRESHOLD_M);
+    let flat_pace = altitude_params.flat_pace_s_per_km.unwrap_or(DEFAULT_FLAT_PACE_S_PER_KM);
+    if flat_pace <= 0.0 {
+        return Err(ApiError::BadRequest("flat_pace_s_per_km must be positive".to_string()));
+    }
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(sport);
+    let options = params.options(profile)?;
+    println!("Sport: {}, processing options: {:?}", sport.as_str(), options);
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    
+    Ok(Json(altitude_exposure(&elevation_data, &band_edges, threshold_m, flat_pace)))
+}
+
+async fn get_topology(
+    Extension(user_id): Extension<String>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<TopologyAnalysis>, ApiError> {
+    println!("=== GET TOPOLOGY ===");
+    println!("Race ID: {}", id);
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let analysis = detect_topology(&gpx_data);
+    println!("Topology: {} ({} laps)", analysis.topology.as_str(), analysis.laps);
+    
+    Ok(Json(analysis))
+}
+
+async fn get_position(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(position_params): Query<PositionQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<TrackPosition>, ApiError> {
+    println!("=== GET POSITION ===");
+    println!("Race ID: {}, km: {}", id, position_params.km);
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let options = params.options(sport_profiles.get(sport))?;
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let distances = &elevation_data.cumulative_distance;
+    let total_m = distances.last().copied().unwrap_or(0.0);
+    
+    let distance_m = position_params.km * 1000.0;
+    if !(0.0..=total_m).contains(&distance_m) {
+        return Err(ApiError::BadRequest(format!(
+            "km must be between 0 and {:.2}", total_m / 1000.0
+        )));
+    }
+    
+    let point = point_at(&gpx_data, distances, distance_m);
+    Ok(Json(TrackPosition {
+        distance_m,
+        lat: point.lat,
+        lon: point.lon,
+        ele: interpolate(distances, &elevation_data.processed_altitude(), distance_m),
+        offset_m: 0.0,
+    }))
+}
+
+async fn locate_position(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(locate_params): Query<LocateQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<LocatedPoint>, ApiError> {
+    println!("=== LOCATE POSITION ===");
+    println!("Race ID: {}, at: {}, {}", id, locate_params.lat, locate_params.lon);
+    
+    if !(-90.0..=90.0).contains(&locate_params.lat) || !(-180.0..=180.0).contains(&locate_params.lon) {
+        return Err(ApiError::BadRequest("lat/lon out of range".to_string()));
+    }
+    let max_offset_m = locate_params.max_offset_m.unwrap_or(DEFAULT_LOCATE_RADIUS_M);
+    if max_offset_m <= 0.0 {
+        return Err(ApiError::BadRequest("max_offset_m must be positive".to_string()));
+    }
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let options = params.options(sport_profiles.get(sport))?;
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let distances = &elevation_data.cumulative_distance;
+    
+    let mut located = locate_on_track(&gpx_data, distances, locate_params.lat, locate_params.lon, max_offset_m);
+    // Report the processed elevation, as the profile shows it
+    let altitudes = elevation_data.processed_altitude();
+    for position in located.nearest.iter_mut().chain(located.passes.iter_mut()) {
+        position.ele = interpolate(distances, &altitudes, position.distance_m);
+    }
+    
+    println!("Located {} passes", located.passes.len());
+    Ok(Json(located))
+}
+
+async fn predict_finish_time(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+    Json(payload): Json<PredictionRequest>,
+) -> Result<Json<PacePrediction>, ApiError> {
+    println!("=== PREDICT FINISH TIME ===");
+    println!("Race ID: {}", id);
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    let options = params.options(sport_profiles.get(sport))?;
+    
+    let (flat_pace, spread, activities_used) = match (payload.flat_pace_s_per_km, payload.activities) {
+        (Some(pace), None) => {
+            if pace <= 0.0 {
+                return Err(ApiError::BadRequest("flat_pace_s_per_km must be positive".to_string()));
+            }
+            (pace, None, 0)
+        }
+        (None, Some(activities)) if !activities.is_empty() => {
+            let mut history = Vec::with_capacity(activities.len());
+            for activity in &activities {
+                if activity.elapsed_seconds <= 0.0 {
+                    return Err(ApiError::BadRequest("elapsed_seconds must be positive".to_string()));
+                }
+                let (activity_gpx, _) = load_gpx_data(&db_pool, &activity.race_id, &user_id).await?;
+                history.push((ElevationData::from_gpx_data(&activity_gpx, &options), activity.elapsed_seconds));
+            }
+            
+            let (pace, spread) = flat_pace_from_activities(&history)
+                .ok_or_else(|| ApiError::BadRequest("Activities have no distance".to_string()))?;
+            println!("Flat pace from {} activities: {:.0}s/km (±{:.1}%)", history.len(), pace, spread * 100.0);
+            (pace, Some(spread), history.len())
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Provide either flat_pace_s_per_km or a non-empty activities list".to_string(),
+            ));
+        }
+    };
+    
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let prediction = predict(&elevation_data, flat_pace, spread, activities_used);
+    
+    println!("Predicted finish: {:.0}s ({:.0}-{:.0}s)",
+        prediction.finish_seconds, prediction.finish_low_seconds, prediction.finish_high_seconds);
+    
+    Ok(Json(prediction))
+}
+
+async fn fetch_checkpoints(db_pool: &SqlitePool, race_id: &str) -> Result<Vec<Checkpoint>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, race_id, name, distance_km, cutoff_time, cutoff_day, created_at
+        FROM checkpoints
+        WHERE race_id = ?
+        ORDER BY distance_km
+        "#,
+        race_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+    
+    Ok(rows.into_iter().map(|row| Checkpoint {
+        id: row.id,
+        race_id: row.race_id,
+        name: row.name,
+        distance_km: row.distance_km,
+        cutoff_time: row.cutoff_time,
+        cutoff_day: row.cutoff_day,
+        created_at: row.created_at,
+    }).collect())
+}
+
+async fn get_checkpoints(
+    Extension(user_id): Extension<String>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<Vec<Checkpoint>>, ApiError> {
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    
+    Ok(Json(fetch_checkpoints(&db_pool, &race.id).await?))
+}
+
+async fn replace_checkpoints(
+    Extension(user_id): Extension<String>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+    Json(payload): Json<Vec<CheckpointInput>>,
+) -> Result<Json<Vec<Checkpoint>>, ApiError> {
+    println!("=== REPLACE CHECKPOINTS ===");
+    println!("Race ID: {}, checkpoints: {}", id, payload.len());
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    
+    for checkpoint in &payload {
+        if checkpoint.name.trim().is_empty() {
+            return Err(ApiError::ValidationError("Checkpoint name is required".to_string()));
+        }
+        if checkpoint.distance_km < 0.0 || checkpoint.distance_km > race.distance_km + 0.5 {
+            return Err(ApiError::ValidationError(format!(
+                "Checkpoint {} is outside the course ({:.1} km)", checkpoint.name, race.distance_km
+            )));
+        }
+        if checkpoint.cutoff_day < 0 {
+            return Err(ApiError::ValidationError("cutoff_day must not be negative".to_string()));
+        }
+        if let Some(cutoff_time) = &checkpoint.cutoff_time {
+            parse_clock_time(cutoff_time).map_err(ApiError::ValidationError)?;
+        }
+    }
+    
+    let mut tx = db_pool.begin().await?;
+    
+    sqlx::query!(r#"DELETE FROM checkpoints WHERE race_id = ?"#, race.id)
+        .execute(&mut *tx)
+        .await?;
+    
+    for checkpoint in &payload {
+        let checkpoint_id = Uuid::new_v4().to_string();
+        let name = checkpoint.name.trim();
+        sqlx::query!(
+            r#"
+            INSERT INTO checkpoints (id, race_id, name, distance_km, cutoff_time, cutoff_day)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            checkpoint_id,
+            race.id,
+            name,
+            checkpoint.distance_km,
+            checkpoint.cutoff_time,
+            checkpoint.cutoff_day
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    
+    tx.commit().await?;
+    
+    Ok(Json(fetch_checkpoints(&db_pool, &race.id).await?))
+}
+
+async fn get_pacing_plan(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(plan_params): Query<PacingPlanQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Response, ApiError> {
+    println!("=== GET PACING PLAN ===");
+    println!("Race ID: {}", id);
+    
+    let start_time = parse_clock_time(&plan_params.start_time).map_err(ApiError::BadRequest)?;
+    let target_seconds = parse_duration(&plan_params.target_time).map_err(ApiError::BadRequest)?;
+    if target_seconds <= 0.0 {
+        return Err(ApiError::BadRequest("target_time must be positive".to_string()));
+    }
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(race_sport(&race));
+    let options = params.options(profile)?;
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let checkpoints = fetch_checkpoints(&db_pool, &race.id).await?;
+    
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let plan = build_pacing_plan(&elevation_data, &checkpoints, start_time, target_seconds);
+    
+    println!("Pacing plan: {} checkpoints, {} missed cut-offs", plan.checkpoints.len(), plan.missed_cutoffs);
+    
+    match plan_params.format.as_deref() {
+        None | Some("json") => Ok(Json(plan).into_response()),
+        Some("csv") => Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"pacing-plan-{}.csv\"", id)),
+            ],
+            pacing_plan_to_csv(&plan),
+        ).into_response()),
+        Some(other) => Err(ApiError::BadRequest(format!("Unknown format: {}", other))),
+    }
+}
+
+async fn get_daylight_plan(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(daylight_params): Query<DaylightQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<DaylightPlan>, ApiError> {
+    println!("=== GET DAYLIGHT PLAN ===");
+    println!("Race ID: {}", id);
+    
+    let start = chrono::DateTime::parse_from_rfc3339(daylight_params.start.trim())
+        .map_err(|_| ApiError::BadRequest(format!("Invalid start: {}", daylight_params.start)))?;
+    let target_seconds = parse_duration(&daylight_params.target_time).map_err(ApiError::BadRequest)?;
+    if target_seconds <= 0.0 {
+        return Err(ApiError::BadRequest("target_time must be positive".to_string()));
+    }
+    if target_seconds > MAX_TARGET_SECONDS {
+        return Err(ApiError::BadRequest("target_time must be at most 30 days".to_string()));
+    }
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(race_sport(&race));
+    let options = params.options(profile)?;
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let checkpoints = fetch_checkpoints(&db_pool, &race.id).await?;
+    
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let plan = build_daylight_plan(&gpx_data, &elevation_data, &checkpoints, start, target_seconds)
+        .map_err(ApiError::BadRequest)?;
+    
+    println!("Daylight plan: {} dark sections, {:.1}km in darkness", plan.dark_sections.len(), plan.dark_distance_km);
+    Ok(Json(plan))
+}
+
+async fn fetch_aid_stations(db_pool: &SqlitePool, race_id: &str) -> Result<Vec<AidStation>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, race_id, name, distance_km, lat, lon, services, created_at
+        FROM aid_stations
+        WHERE race_id = ?
+        ORDER BY distance_km
+        "#,
+        race_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+    
+    rows.into_iter().map(|row| Ok(AidStation {
+        id: row.id,
+        race_id: row.race_id,
+        name: row.name,
+        distance_km: row.distance_km,
+        lat: row.lat,
+        lon: row.lon,
+        services: serde_json::from_str(&row.services)?,
+        created_at: row.created_at,
+    })).collect()
+}
+
+async fn get_aid_stations(
+    Extension(user_id): Extension<String>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<Vec<AidStation>>, ApiError> {
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    
+    Ok(Json(fetch_aid_stations(&db_pool, &race.id).await?))
+}
+
+async fn replace_aid_stations(
+    Extension(user_id): Extension<String>,
+    Path(id): Path<String>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+    Json(payload): Json<Vec<AidStationInput>>,
+) -> Result<Json<Vec<AidStation>>, ApiError> {
+    println!("=== REPLACE AID STATIONS ===");
+    println!("Race ID: {}, aid stations: {}", id, payload.len());
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let distances = cumulative_distances(&gpx_data);
+    let total_m = distances.last().copied().unwrap_or(0.0);
+    
+    // Resolve every station to a point on the track before touching the table
+    let mut snapped = Vec::with_capacity(payload.len());
+    for station in &payload {
+        if station.name.trim().is_empty() {
+            return Err(ApiError::ValidationError("Aid station name is required".to_string()));
+        }
+        
+        let (distance_m, lat, lon) = match (station.distance_km, station.lat, station.lon) {
+            (Some(distance_km), _, _) => {
+                if distance_km < 0.0 || distance_km * 1000.0 > total_m + 500.0 {
+                    return Err(ApiError::ValidationError(format!(
+                        "Aid station {} is outside the course ({:.1} km)", station.name, total_m / 1000.0
+                    )));
+                }
+                let distance_m = (distance_km * 1000.0).min(total_m);
+                let point = point_at(&gpx_data, &distances, distance_m);
+                (distance_m, point.lat, point.lon)
+            }
+            (None, Some(lat), Some(lon)) => {
+                let position = snap_to_track(&gpx_data, &distances, lat, lon)
+                    .ok_or_else(|| ApiError::BadRequest("Race has no track points".to_string()))?;
+                if position.offset_m > MAX_SNAP_OFFSET_M {
+                    return Err(ApiError::ValidationError(format!(
+                        "Aid station {} is {:.0} m from the course", station.name, position.offset_m
+                    )));
+                }
+                (position.distance_m, position.lat, position.lon)
+            }
+            _ => {
+                return Err(ApiError::ValidationError(format!(
+                    "Aid station {} needs distance_km or lat and lon", station.name
+                )));
+            }
+        };
+        
+        snapped.push((station, distance_m / 1000.0, lat, lon));
+    }
+    
+    let mut tx = db_pool.begin().await?;
+    
+    sqlx::query!(r#"DELETE FROM aid_stations WHERE race_id = ?"#, race.id)
+        .execute(&mut *tx)
+        .await?;
+    
+    for (station, distance_km, lat, lon) in snapped {
+        let station_id = Uuid::new_v4().to_string();
+        let name = station.name.trim();
+        let services = serde_json::to_string(&station.services)?;
+        sqlx::query!(
+            r#"
+            INSERT INTO aid_stations (id, race_id, name, distance_km, lat, lon, services)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            station_id,
+            race.id,
+            name,
+            distance_km,
+            lat,
+            lon,
+            services
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    
+    tx.commit().await?;
+    
+    Ok(Json(fetch_aid_stations(&db_pool, &race.id).await?))
+}
+
+async fn get_legs(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    Query(bin_params): Query<GradientBinQuery>,
+    Query(effort_params): Query<EffortQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+) -> Result<Json<Vec<Leg>>, ApiError> {
+    println!("=== GET LEGS ===");
+    println!("Race ID: {}", id);
+    
+    let race = fetch_race(&db_pool, &id, &user_id).await?;
+    let profile = sport_profiles.get(race_sport(&race));
+    let options = params.options(profile)?;
+    let bins = bin_params.bins(profile)?;
+    let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+    let aid_stations = fetch_aid_stations(&db_pool, &race.id).await?;
+    
+    let legs = build_legs(&gpx_data, &aid_stations, &options, &bins, effort_params.effort_model.unwrap_or(profile.effort_model));
+    
+    Ok(Json(legs))
+}
+
+async fn estimate_cycling_power(
+    Extension(user_id): Extension<String>,
+    Extension(sport_profiles): Extension<Arc<SportProfileRegistry>>,
+    Path(id): Path<String>,
+    Query(params): Query<ProcessingQuery>,
+    State((db_pool, _)): State<(SqlitePool, Settings)>,
+    Json(payload): Json<PowerRequest>,
+) -> Result<Json<PowerEstimate>, ApiError> {
+    println!("=== ESTIMATE CYCLING POWER ===");
+    println!("Race ID: {}", id);
+    
+    let target = payload.target()?;
+    let setup = payload.setup()?;
+    
+    let (gpx_data, sport) = load_gpx_data(&db_pool, &id, &user_id).await?;
+    if !sport.is_cycling() {
+        return Err(ApiError::BadRequest(format!(
+            "Power estimates need a cycling race, this one is {}", sport.as_str()
+        )));
+    }
+    let profile = sport_profiles.get(sport);
+    let options = params.options(profile)?;
+    println!("Sport: {}, processing options: {:?}", sport.as_str(), options);
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    let estimate = estimate_power(&elevation_data, &setup, target);
+    
+    println!("Power estimate: {:.0}s, {:.1}km/h, {:.0}W, {:.0}kJ",
+        estimate.total_time_seconds, estimate.average_speed_kmh, estimate.average_power_w, estimate.total_energy_kj);
+    
+    Ok(Json(estimate))
+}
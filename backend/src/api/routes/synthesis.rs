@@ -11,6 +11,11 @@ use uuid::Uuid;
 
 use crate::api::middleware::auth::auth_middleware;
 use crate::config::settings::Settings;
+use crate::core::algorithms::route_matching::{filter_routes_by_bbox, find_similar_routes, RouteMatchingConfig};
+use crate::core::algorithms::terrain_classification::TerrainClass;
+use crate::core::models::race::GpxData;
+use crate::core::models::synthesis::{self, RouteCandidate, RouteData, RoutePoint};
+use crate::core::services::effort_model::EffortModelKind;
 use crate::errors::handlers::ApiError;
 
 #[derive(Debug, Deserialize)]
@@ -19,6 +24,12 @@ pub struct SynthesisRequest {
     bounding_box: BoundingBox,
     rolling_window: u32,
     max_results: u32,
+    /// Effort model used to score candidates against the reference
+    #[serde(default)]
+    effort_model: EffortModelKind,
+    /// Terrain classes candidates are restricted to; empty allows any
+    #[serde(default)]
+    terrain: Vec<TerrainClass>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -48,19 +59,43 @@ async fn generate_routes(
     Json(payload): Json<SynthesisRequest>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
     // Verify user owns the reference race
-    let _race = sqlx::query!(
-        r#"SELECT id FROM races WHERE id = ? AND user_id = ?"#,
+    let race = sqlx::query!(
+        r#"SELECT id, gpx_data, topology, laps FROM races WHERE id = ? AND user_id = ?"#,
         payload.reference_race_id,
         user_id
     )
     .fetch_optional(&db_pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Reference race not found".to_string()))?;
+    let reference: GpxData = serde_json::from_str(&race.gpx_data)?;
+    
+    // Candidates are the user's other courses inside the bounding box
+    let bbox = synthesis::BoundingBox {
+        north: payload.bounding_box.north,
+        south: payload.bounding_box.south,
+        east: payload.bounding_box.east,
+        west: payload.bounding_box.west,
+    };
+    let candidates = filter_routes_by_bbox(
+        library_candidates(&db_pool, &user_id, &payload.reference_race_id).await?,
+        &bbox,
+    );
+    println!("Synthesis candidates in bounding box: {}", candidates.len());
+    
+    let config = RouteMatchingConfig {
+        max_results: payload.max_results as usize,
+        effort_model: payload.effort_model,
+        terrain: payload.terrain.clone(),
+        ..Default::default()
+    };
+    let results = tokio::task::spawn_blocking(move || find_similar_routes(&reference, candidates, config))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Route matching failed: {}", e)))?;
     
     // Create synthesis job
     let synthesis_id = Uuid::new_v4().to_string();
     let bbox_json = serde_json::to_string(&payload.bounding_box)?;
-    let results_json = serde_json::to_string(&Vec::<serde_json::Value>::new())?;
+    let results_json = serde_json::to_string(&results)?;
     
     sqlx::query!(
         r#"
@@ -78,17 +113,57 @@ async fn generate_routes(
     .execute(&db_pool)
     .await?;
     
-    // In real implementation, this would start an async job
-    // For now, return immediate response
     Ok(Json(serde_json::json!({
         "id": synthesis_id,
         "user_id": user_id,
         "reference_race_id": payload.reference_race_id,
-        "results": [],
+        "effort_model": payload.effort_model,
+        "reference_topology": race.topology,
+        "reference_laps": race.laps,
+        "terrain": payload.terrain,
+        "results": results,
         "created_at": chrono::Utc::now().to_rfc3339()
     })))
 }
 
+/// Every race in the user's library except the reference, as route candidates
+async fn library_candidates(
+    db_pool: &SqlitePool,
+    user_id: &str,
+    reference_race_id: &str,
+) -> Result<Vec<RouteCandidate>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, gpx_data, distance_km, elevation_gain_m, elevation_loss_m, itra_effort_distance, terrain
+        FROM races WHERE user_id = ? AND id != ?
+        "#,
+        user_id,
+        reference_race_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+    
+    rows.into_iter()
+        .map(|row| {
+            let gpx_data: GpxData = serde_json::from_str(&row.gpx_data)?;
+            Ok(RouteCandidate {
+                id: row.id,
+                distance_km: row.distance_km,
+                elevation_gain_m: row.elevation_gain_m,
+                elevation_loss_m: row.elevation_loss_m,
+                itra_effort_distance: row.itra_effort_distance.unwrap_or(0.0),
+                terrain: row.terrain,
+                similarity_score: 0.0,
+                route: RouteData {
+                    points: gpx_data.points.iter()
+                        .map(|p| RoutePoint { lat: p.lat, lon: p.lon, ele: p.ele })
+                        .collect(),
+                },
+            })
+        })
+        .collect()
+}
+
 async fn get_synthesis_results(
     Extension(user_id): Extension<String>,
     Path(id): Path<String>,
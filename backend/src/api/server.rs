@@ -4,13 +4,28 @@ use axum::{
     extract::DefaultBodyLimit,
 };
 use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
 use tower_http::cors::{CorsLayer, Any};
 use tower_http::trace::TraceLayer;
 
 use crate::config::settings::Settings;
 use crate::api::routes;
+use crate::core::services::dem_service::DemService;
+use crate::core::services::sport_profiles::SportProfileRegistry;
 
-pub fn create_app(db_pool: SqlitePool, settings: Settings) -> Router {
+/// Built-in sport profiles, with any overrides from the profiles file
+pub fn load_sport_profiles(settings: &Settings) -> SportProfileRegistry {
+    match &settings.sport_profiles_path {
+        Some(path) => SportProfileRegistry::load(Path::new(path)).unwrap_or_else(|e| {
+            tracing::warn!("Sport profiles {} unavailable, using defaults: {}", path, e);
+            SportProfileRegistry::default()
+        }),
+        None => SportProfileRegistry::default(),
+    }
+}
+
+pub fn create_app(db_pool: SqlitePool, settings: Settings, sport_profiles: Arc<SportProfileRegistry>) -> Router {
     // CORS configuration - allow all origins in development
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -18,6 +33,17 @@ pub fn create_app(db_pool: SqlitePool, settings: Settings) -> Router {
         .allow_headers(Any)
         .expose_headers(Any);
     
+    // DEM tiles are optional; without them elevation correction is unavailable
+    let dem_service = settings.dem_directory.as_ref().and_then(|dir| {
+        match DemService::new(Path::new(dir), settings.dem_cache_mb * 1024 * 1024) {
+            Ok(service) => Some(Arc::new(service)),
+            Err(e) => {
+                tracing::warn!("DEM directory {} unavailable: {}", dir, e);
+                None
+            }
+        }
+    });
+    
     // Create router with increased body limit for file uploads
     Router::new()
         .nest("/api/v1", api_routes(db_pool.clone(), settings.clone()))
@@ -25,6 +51,8 @@ pub fn create_app(db_pool: SqlitePool, settings: Settings) -> Router {
         .layer(cors)
         .layer(TraceLayer::new_for_http())
         .layer(Extension(db_pool))
+        .layer(Extension(dem_service))
+        .layer(Extension(sport_profiles))
         .layer(Extension(settings))
 }
 
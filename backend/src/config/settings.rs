@@ -10,6 +10,10 @@ pub struct Settings {
     pub host: String,
     pub log_level: String,
     pub cors_origin: String,
+    pub dem_directory: Option<String>,
+    /// Memory for decoded DEM tiles, in megabytes
+    pub dem_cache_mb: usize,
+    pub sport_profiles_path: Option<String>,
 }
 
 impl Settings {
@@ -31,6 +35,13 @@ impl Settings {
                 .unwrap_or_else(|_| "info".to_string()),
             cors_origin: env::var("CORS_ORIGIN")
                 .unwrap_or_else(|_| "*".to_string()),
+            dem_directory: env::var("DEM_DIRECTORY").ok()
+                .filter(|dir| !dir.is_empty()),
+            dem_cache_mb: env::var("DEM_CACHE_MB")
+                .unwrap_or_else(|_| "512".to_string())
+                .parse()?,
+            sport_profiles_path: env::var("SPORT_PROFILES_PATH").ok()
+                .filter(|path| !path.is_empty()),
         })
     }
     
@@ -57,6 +68,9 @@ impl Default for Settings {
             host: "127.0.0.1".to_string(),
             log_level: "info".to_string(),
             cors_origin: "*".to_string(),
+            dem_directory: None,
+            dem_cache_mb: 512,
+            sport_profiles_path: None,
         }
     }
 }
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::services::elevation_processor::ElevationData;
+use crate::core::services::pace_predictor::pace_factor;
+
+/// Edges between the default altitude bands, in metres
+pub const DEFAULT_BAND_EDGES_M: [f64; 3] = [1500.0, 2500.0, 3500.0];
+
+/// Default altitude above which the longest continuous section is measured
+pub const DEFAULT_THRESHOLD_M: f64 = 2500.0;
+
+/// Flat pace used for time estimates when none is given, in seconds per km
+pub const DEFAULT_FLAT_PACE_S_PER_KM: f64 = 360.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AltitudeBand {
+    /// Lower bound in metres; none for the lowest band
+    pub min_m: Option<f64>,
+    /// Upper bound in metres; none for the highest band
+    pub max_m: Option<f64>,
+    pub distance_km: f64,
+    /// Share of the course distance, 0 to 1
+    pub share: f64,
+    pub time_seconds: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighPoint {
+    pub altitude_m: f64,
+    pub distance_km: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AltitudeSection {
+    pub start_km: f64,
+    pub end_km: f64,
+    pub distance_km: f64,
+    pub time_seconds: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AltitudeExposure {
+    pub bands: Vec<AltitudeBand>,
+    pub highest_point: Option<HighPoint>,
+    pub threshold_m: f64,
+    pub longest_above_threshold: Option<AltitudeSection>,
+    pub distance_above_threshold_km: f64,
+    pub flat_pace_s_per_km: f64,
+}
+
+/// Distance and estimated time in each altitude band, the highest point and
+/// the longest continuous stretch above `threshold_m`.
+///
+/// Each step between two points counts towards the band of its mean altitude;
+/// times come from `flat_pace_s_per_km` adjusted for the step's gradient.
+pub fn altitude_exposure(
+    elevation_data: &ElevationData,
+    band_edges_m: &[f64],
+    threshold_m: f64,
+    flat_pace_s_per_km: f64,
+) -> AltitudeExposure {
+    let distances = &elevation_data.cumulative_distance;
+    let altitudes = &elevation_data.processed_altitude();
+    let total_m = distances.last().copied().unwrap_or(0.0);
+
+    let mut edges = band_edges_m.to_vec();
+    edges.sort_by(f64::total_cmp);
+    edges.dedup();
+
+    let mut bands: Vec<AltitudeBand> = (0..=edges.len())
+        .map(|i| AltitudeBand {
+            min_m: i.checked_sub(1).map(|j| edges[j]),
+            max_m: edges.get(i).copied(),
+            distance_km: 0.0,
+            share: 0.0,
+            time_seconds: 0.0,
+        })
+        .collect();
+
+    let mut longest: Option<AltitudeSection> = None;
+    let mut current: Option<AltitudeSection> = None;
+    let mut above_m = 0.0;
+
+    for i in 1..distances.len().min(altitudes.len()) {
+        let distance_m = elevation_data.distance_change[i];
+        if distance_m <= 0.0 {
+            continue;
+        }
+        let altitude = (altitudes[i - 1] + altitudes[i]) / 2.0;
+        let seconds = distance_m / 1000.0 * flat_pace_s_per_km * pace_factor(elevation_data.gradient_percent[i]);
+
+        let band = edges.iter().take_while(|edge| altitude >= **edge).count();
+        bands[band].distance_km += distance_m / 1000.0;
+        bands[band].time_seconds += seconds;
+
+        if altitude >= threshold_m {
+            above_m += distance_m;
+            let section = current.get_or_insert(AltitudeSection {
+                start_km: distances[i - 1] / 1000.0,
+                end_km: distances[i - 1] / 1000.0,
+                distance_km: 0.0,
+                time_seconds: 0.0,
+            });
+            section.end_km = distances[i] / 1000.0;
+            section.distance_km += distance_m / 1000.0;
+            section.time_seconds += seconds;
+        } else if let Some(section) = current.take() {
+            if longest.as_ref().is_none_or(|l| section.distance_km > l.distance_km) {
+                longest = Some(section);
+            }
+        }
+    }
+    if let Some(section) = current {
+        if longest.as_ref().is_none_or(|l| section.distance_km > l.distance_km) {
+            longest = Some(section);
+        }
+    }
+
+    if total_m > 0.0 {
+        for band in &mut bands {
+            band.share = band.distance_km * 1000.0 / total_m;
+        }
+    }
+
+    let highest_point = altitudes.iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(i, altitude)| HighPoint {
+            altitude_m: *altitude,
+            distance_km: distances.get(i).copied().unwrap_or(0.0) / 1000.0,
+        });
+
+    AltitudeExposure {
+        bands,
+        highest_point,
+        threshold_m,
+        longest_above_threshold: longest,
+        distance_above_threshold_km: above_m / 1000.0,
+        flat_pace_s_per_km,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::processing::ProcessingOptions;
+    use crate::core::test_support::straight_course;
+
+    #[test]
+    fn test_altitude_bands_and_longest_section() {
+        // ~11 km: 1 km low, 3 km above 2500 m, 2 km low, 5 km above 2500 m
+        let (_, elevation_data) = straight_course(1001, |d| {
+            if (1000.0..4000.0).contains(&d) || d >= 6000.0 { 2800.0 } else { 1000.0 }
+        });
+
+        let exposure = altitude_exposure(&elevation_data, &DEFAULT_BAND_EDGES_M, 2500.0, 360.0);
+
+        assert_eq!(exposure.bands.len(), 4);
+        assert!((exposure.bands[0].distance_km - 3.0).abs() < 0.05);
+        assert!((exposure.bands[2].distance_km - 8.1).abs() < 0.05);
+        assert!(exposure.bands[1].distance_km < 0.05);
+        assert_eq!(exposure.highest_point.unwrap().altitude_m, 2800.0);
+
+        let longest = exposure.longest_above_threshold.unwrap();
+        assert!((longest.start_km - 6.0).abs() < 0.05);
+        assert!((longest.distance_km - 5.1).abs() < 0.05);
+        // Flat ground at 6:00/km
+        assert!((longest.time_seconds - longest.distance_km * 360.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_smoothing_moves_band_crossing() {
+        // ~5.5 km just under 2500 m with a two-point GPS spike above it
+        let (gpx_data, raw) = straight_course(500, |d| if (2000.0..2020.0).contains(&d) { 2550.0 } else { 2450.0 });
+        let options = ProcessingOptions {
+            smoothed: true,
+            capping: false,
+            ..Default::default()
+        };
+        let smoothed = ElevationData::from_gpx_data(&gpx_data, &options);
+        assert!(smoothed.smoothing_applied);
+
+        let raw = altitude_exposure(&raw, &DEFAULT_BAND_EDGES_M, 2500.0, 360.0);
+        assert!(raw.distance_above_threshold_km > 0.0);
+        assert!(raw.bands[2].distance_km > 0.0);
+
+        let smoothed = altitude_exposure(&smoothed, &DEFAULT_BAND_EDGES_M, 2500.0, 360.0);
+        assert_eq!(smoothed.distance_above_threshold_km, 0.0);
+        assert_eq!(smoothed.bands[2].distance_km, 0.0);
+        assert!(smoothed.highest_point.unwrap().altitude_m < 2500.0);
+    }
+}
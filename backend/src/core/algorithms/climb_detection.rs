@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::algorithms::gradient_analysis::max_gradient;
+use crate::core::services::elevation_processor::ElevationData;
+
+/// Distance over which a climb's maximum gradient is measured, in metres
+const MAX_GRADIENT_WINDOW_M: f64 = 100.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Climb {
+    pub start_km: f64,
+    pub end_km: f64,
+    pub length_m: f64,
+    pub elevation_gain_m: f64,
+    pub start_elevation_m: f64,
+    pub top_elevation_m: f64,
+    pub average_gradient: f64,
+    pub max_gradient: f64,
+    /// Length in metres times average gradient in percent
+    pub score: f64,
+    pub category: Option<String>,
+}
+
+/// Scale used to put climbs into categories
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClimbScale {
+    /// Cat 4 (easiest) to HC, from the climb score
+    Cycling,
+    /// T1 (easiest) to T5, from the elevation gain
+    Trail,
+}
+
+impl ClimbScale {
+    pub fn categorize(&self, score: f64, elevation_gain_m: f64) -> Option<&'static str> {
+        let thresholds: [(f64, &'static str); 5] = match self {
+            ClimbScale::Cycling => [
+                (80_000.0, "HC"),
+                (64_000.0, "1"),
+                (32_000.0, "2"),
+                (16_000.0, "3"),
+                (8_000.0, "4"),
+            ],
+            ClimbScale::Trail => [
+                (1500.0, "T5"),
+                (1000.0, "T4"),
+                (600.0, "T3"),
+                (300.0, "T2"),
+                (100.0, "T1"),
+            ],
+        };
+        let value = match self {
+            ClimbScale::Cycling => score,
+            ClimbScale::Trail => elevation_gain_m,
+        };
+
+        thresholds.iter()
+            .find(|(threshold, _)| value >= *threshold)
+            .map(|(_, label)| *label)
+    }
+}
+
+pub struct ClimbDetectionConfig {
+    /// Descent from the highest point that ends a climb; smaller dips are absorbed
+    pub max_dip_m: f64,
+    pub min_gain_m: f64,
+    pub min_average_gradient: f64,
+    pub scale: ClimbScale,
+}
+
+impl Default for ClimbDetectionConfig {
+    fn default() -> Self {
+        Self {
+            max_dip_m: 20.0,
+            min_gain_m: 30.0,
+            min_average_gradient: 3.0,
+            scale: ClimbScale::Cycling,
+        }
+    }
+}
+
+/// Find climbs in processed elevation data.
+///
+/// A climb starts at a low point once the track has risen `max_dip_m` above
+/// it and ends at its highest point once the track drops `max_dip_m` below that.
+pub fn detect_climbs(elevation_data: &ElevationData, config: &ClimbDetectionConfig) -> Vec<Climb> {
+    let distances = &elevation_data.cumulative_distance;
+    let altitudes = elevation_data.processed_altitude();
+    let mut climbs = Vec::new();
+
+    if altitudes.len() < 2 {
+        return climbs;
+    }
+
+    let mut low = 0;
+    let mut high: Option<usize> = None;
+
+    for i in 1..altitudes.len() {
+        match high {
+            None => {
+                // Latest lowest point, so a flat approach is not counted
+                if altitudes[i] <= altitudes[low] {
+                    low = i;
+                } else if altitudes[i] - altitudes[low] >= config.max_dip_m {
+                    high = Some(i);
+                }
+            }
+            Some(top) => {
+                if altitudes[i] > altitudes[top] {
+                    high = Some(i);
+                } else if altitudes[top] - altitudes[i] >= config.max_dip_m {
+                    climbs.extend(build_climb(distances, &altitudes, low, top, config));
+                    high = None;
+                    low = i;
+                }
+            }
+        }
+    }
+
+    if let Some(top) = high {
+        climbs.extend(build_climb(distances, &altitudes, low, top, config));
+    }
+
+    climbs
+}
+
+fn build_climb(
+    distances: &[f64],
+    altitudes: &[f64],
+    start: usize,
+    end: usize,
+    config: &ClimbDetectionConfig,
+) -> Option<Climb> {
+    let length_m = distances[end] - distances[start];
+    let elevation_gain_m = altitudes[end] - altitudes[start];
+
+    if length_m <= 0.0 || elevation_gain_m < config.min_gain_m {
+        return None;
+    }
+
+    let average_gradient = elevation_gain_m / length_m * 100.0;
+    if average_gradient < config.min_average_gradient {
+        return None;
+    }
+
+    let score = length_m * average_gradient;
+
+    Some(Climb {
+        start_km: distances[start] / 1000.0,
+        end_km: distances[end] / 1000.0,
+        length_m,
+        elevation_gain_m,
+        start_elevation_m: altitudes[start],
+        top_elevation_m: altitudes[end],
+        average_gradient,
+        max_gradient: max_gradient(&distances[start..=end], &altitudes[start..=end], MAX_GRADIENT_WINDOW_M),
+        score,
+        category: config.scale.categorize(score, elevation_gain_m).map(str::to_string),
+    })
+}
+
+/// How alike two routes' climb structures are, from 0 to 1.
+///
+/// Compares total climbing in categorised climbs, the biggest single climb
+/// and the number of climbs.
+pub fn climb_similarity(reference: &[Climb], candidate: &[Climb]) -> f64 {
+    fn ratio(a: f64, b: f64) -> f64 {
+        if a <= 0.0 && b <= 0.0 {
+            1.0
+        } else {
+            a.min(b) / a.max(b)
+        }
+    }
+
+    let total = |climbs: &[Climb]| climbs.iter().map(|c| c.elevation_gain_m).sum::<f64>();
+    let biggest = |climbs: &[Climb]| climbs.iter().map(|c| c.elevation_gain_m).fold(0.0, f64::max);
+
+    let total_ratio = ratio(total(reference), total(candidate));
+    let biggest_ratio = ratio(biggest(reference), biggest(candidate));
+    let count_ratio = ratio(reference.len() as f64, candidate.len() as f64);
+
+    0.4 * total_ratio + 0.4 * biggest_ratio + 0.2 * count_ratio
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    fn track(points: usize, ele: impl Fn(f64) -> f64) -> ElevationData {
+        straight_course(points, ele).1
+    }
+
+    #[test]
+    fn test_small_dip_does_not_split_climb() {
+        // 2 km at 6% with a 10 m dip in the middle, then a descent
+        let data = track(300, |d| {
+            if d < 1000.0 {
+                d * 0.06
+            } else if d < 1100.0 {
+                60.0 - (d - 1000.0) * 0.1
+            } else if d < 2100.0 {
+                50.0 + (d - 1100.0) * 0.07
+            } else {
+                120.0 - (d - 2100.0) * 0.1
+            }
+        });
+
+        let climbs = detect_climbs(&data, &ClimbDetectionConfig::default());
+
+        assert_eq!(climbs.len(), 1);
+        assert!((climbs[0].elevation_gain_m - 120.0).abs() < 2.0);
+        assert!(climbs[0].start_km < 0.05);
+        assert!((climbs[0].end_km - 2.1).abs() < 0.05);
+        assert!(climbs[0].max_gradient >= 6.9);
+    }
+
+    #[test]
+    fn test_categories() {
+        assert_eq!(ClimbScale::Cycling.categorize(10_000.0 * 8.0, 800.0), Some("HC"));
+        assert_eq!(ClimbScale::Cycling.categorize(2_000.0 * 5.0, 100.0), Some("4"));
+        assert_eq!(ClimbScale::Cycling.categorize(500.0 * 4.0, 20.0), None);
+        assert_eq!(ClimbScale::Trail.categorize(0.0, 650.0), Some("T3"));
+    }
+}
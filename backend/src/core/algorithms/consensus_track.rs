@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::algorithms::linear_referencing::{cumulative_distances, distance_m, point_at, snap_candidates};
+use crate::core::models::race::{GpxData, GpxPoint};
+
+/// Spacing of the consensus track, in metres
+const STATION_SPACING_M: f64 = 10.0;
+
+/// How far along a recording to look either side of where a station should be
+const SEARCH_WINDOW_M: f64 = 500.0;
+
+/// A recording further than this from the reference is taken at its
+/// proportional distance instead, in metres
+const MATCH_RADIUS_M: f64 = 100.0;
+
+/// Recordings starting or finishing further apart than this are not the same course
+const MAX_END_OFFSET_M: f64 = 500.0;
+
+/// Nor are recordings whose lengths differ by more than this share
+const MAX_LENGTH_DIFFERENCE: f64 = 0.2;
+
+/// How much the recordings disagree over one kilometre of the consensus track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KmSpread {
+    pub start_km: f64,
+    pub end_km: f64,
+    /// Median distance of the recordings from the consensus line, averaged over the km
+    pub horizontal_spread_m: f64,
+    pub max_horizontal_spread_m: f64,
+    /// Median elevation difference from the consensus, averaged over the km
+    pub elevation_spread_m: f64,
+    pub max_elevation_spread_m: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsensusTrack {
+    pub gpx_data: GpxData,
+    pub spread: Vec<KmSpread>,
+}
+
+/// Combine several recordings of the same course into one track.
+///
+/// Every recording is sampled at stations along the one of median length,
+/// and each station takes the median position and elevation of the samples.
+pub fn consensus_track(recordings: &[GpxData]) -> Result<ConsensusTrack, String> {
+    if recordings.len() < 2 {
+        return Err("A consensus needs at least two recordings".to_string());
+    }
+    if recordings.iter().any(|recording| recording.points.len() < 2) {
+        return Err("Every recording needs at least two points".to_string());
+    }
+
+    let distances: Vec<Vec<f64>> = recordings.iter().map(cumulative_distances).collect();
+    let lengths: Vec<f64> = distances.iter().map(|d| d.last().copied().unwrap_or(0.0)).collect();
+
+    let mut by_length: Vec<usize> = (0..recordings.len()).collect();
+    by_length.sort_by(|&a, &b| lengths[a].total_cmp(&lengths[b]));
+    let reference = by_length[by_length.len() / 2];
+    let reference_track = &recordings[reference];
+    let reference_length = lengths[reference];
+    if reference_length <= 0.0 {
+        return Err("Recordings have no length".to_string());
+    }
+
+    for (i, recording) in recordings.iter().enumerate() {
+        let start_offset = distance_m(&recording.points[0], &reference_track.points[0]);
+        let finish_offset = distance_m(
+            recording.points.last().unwrap(),
+            reference_track.points.last().unwrap(),
+        );
+        let length_difference = (lengths[i] - reference_length).abs() / reference_length;
+        if start_offset > MAX_END_OFFSET_M || finish_offset > MAX_END_OFFSET_M || length_difference > MAX_LENGTH_DIFFERENCE {
+            return Err(format!("Recording {} does not follow the same course", i + 1));
+        }
+    }
+
+    let station_count = (reference_length / STATION_SPACING_M).ceil() as usize + 1;
+    let mut points = Vec::with_capacity(station_count);
+    let mut station_spread = Vec::with_capacity(station_count);
+
+    for s in 0..station_count {
+        let station_m = (s as f64 * STATION_SPACING_M).min(reference_length);
+        let anchor = point_at(reference_track, &distances[reference], station_m);
+
+        let samples: Vec<GpxPoint> = recordings.iter()
+            .zip(&distances)
+            .zip(&lengths)
+            .enumerate()
+            .map(|(i, ((recording, recording_distances), length))| {
+                if i == reference {
+                    return anchor.clone();
+                }
+                let expected_m = station_m / reference_length * length;
+                sample_near(recording, recording_distances, &anchor, expected_m)
+            })
+            .collect();
+
+        let consensus = GpxPoint {
+            lat: median(samples.iter().map(|p| p.lat).collect()),
+            lon: median(samples.iter().map(|p| p.lon).collect()),
+            ele: median(samples.iter().map(|p| p.ele).collect()),
+            time: None,
+        };
+        let horizontal = median(samples.iter().map(|p| distance_m(p, &consensus)).collect());
+        let elevation = median(samples.iter().map(|p| (p.ele - consensus.ele).abs()).collect());
+
+        points.push(consensus);
+        station_spread.push((station_m, horizontal, elevation));
+    }
+
+    Ok(ConsensusTrack {
+        gpx_data: GpxData { points },
+        spread: spread_per_km(&station_spread, reference_length),
+    })
+}
+
+/// The recording's closest point to `anchor` near where it is expected,
+/// or the point at that distance when the recording is nowhere near
+fn sample_near(recording: &GpxData, distances: &[f64], anchor: &GpxPoint, expected_m: f64) -> GpxPoint {
+    let from = distances.partition_point(|d| *d < expected_m - SEARCH_WINDOW_M).saturating_sub(1);
+    let to = distances.partition_point(|d| *d <= expected_m + SEARCH_WINDOW_M).min(distances.len() - 1) + 1;
+
+    snap_candidates(&recording.points[from..to], &distances[from..to], anchor.lat, anchor.lon)
+        .filter(|candidate| candidate.offset_m <= MATCH_RADIUS_M)
+        .min_by(|a, b| a.offset_m.total_cmp(&b.offset_m))
+        .map(|candidate| GpxPoint { lat: candidate.lat, lon: candidate.lon, ele: candidate.ele, time: None })
+        .unwrap_or_else(|| point_at(recording, distances, expected_m))
+}
+
+fn spread_per_km(stations: &[(f64, f64, f64)], length_m: f64) -> Vec<KmSpread> {
+    let km_count = (length_m / 1000.0).ceil().max(1.0) as usize;
+    let mut buckets: Vec<Vec<(f64, f64)>> = vec![Vec::new(); km_count];
+    for &(distance, horizontal, elevation) in stations {
+        buckets[((distance / 1000.0) as usize).min(km_count - 1)].push((horizontal, elevation));
+    }
+
+    buckets.iter()
+        .enumerate()
+        .filter(|(_, bucket)| !bucket.is_empty())
+        .map(|(km, bucket)| {
+            let count = bucket.len() as f64;
+            KmSpread {
+                start_km: km as f64,
+                end_km: ((km + 1) as f64).min(length_m / 1000.0),
+                horizontal_spread_m: bucket.iter().map(|s| s.0).sum::<f64>() / count,
+                max_horizontal_spread_m: bucket.iter().map(|s| s.0).fold(0.0, f64::max),
+                elevation_spread_m: bucket.iter().map(|s| s.1).sum::<f64>() / count,
+                max_elevation_spread_m: bucket.iter().map(|s| s.1).fold(0.0, f64::max),
+            }
+        })
+        .collect()
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::{straight_course, POINT_SPACING_M};
+
+    /// ~3.3 km northwards, climbing 1 m per point, shifted `east_m` to the east and `ele_offset` up
+    fn recording(east_m: f64, ele_offset: f64) -> GpxData {
+        let (mut gpx_data, _) = straight_course(300, |d| 500.0 + d / POINT_SPACING_M + ele_offset);
+        for point in gpx_data.points.iter_mut() {
+            point.lon += east_m / (111_320.0 * 46f64.to_radians().cos());
+        }
+        gpx_data
+    }
+
+    #[test]
+    fn test_median_of_recordings() {
+        let recordings = vec![recording(-6.0, -4.0), recording(0.0, 0.0), recording(6.0, 30.0)];
+        let consensus = consensus_track(&recordings).unwrap();
+
+        let middle = &consensus.gpx_data.points[100];
+        assert!((middle.lon - 7.0).abs() < 1e-6);
+        assert!((middle.ele - (500.0 + 1000.0 / POINT_SPACING_M)).abs() < 2.0, "{}", middle.ele);
+
+        assert_eq!(consensus.spread.len(), 4);
+        let first_km = &consensus.spread[0];
+        assert!((first_km.horizontal_spread_m - 6.0).abs() < 0.5, "{}", first_km.horizontal_spread_m);
+        assert!((first_km.elevation_spread_m - 4.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_different_courses_rejected() {
+        let mut other = recording(0.0, 0.0);
+        for point in other.points.iter_mut() {
+            point.lon += 0.05;
+        }
+        assert!(consensus_track(&[recording(0.0, 0.0), other]).is_err());
+        assert!(consensus_track(&[recording(0.0, 0.0)]).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::algorithms::linear_referencing::point_at;
+use crate::core::algorithms::resampling::interpolate;
+use crate::core::algorithms::splits::SplitUnit;
+use crate::core::models::race::GpxData;
+use crate::core::services::elevation_processor::ElevationData;
+
+/// A distance marker along the course, e.g. "km 10"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistanceMarker {
+    pub name: String,
+    /// Distance from the start in the marker unit
+    pub distance: f64,
+    pub distance_m: f64,
+    pub lat: f64,
+    pub lon: f64,
+    pub ele: f64,
+}
+
+/// A marker every `interval` units from the start; the start and finish get none
+pub fn distance_markers(
+    gpx_data: &GpxData,
+    elevation_data: &ElevationData,
+    interval: f64,
+    unit: SplitUnit,
+) -> Vec<DistanceMarker> {
+    let distances = &elevation_data.cumulative_distance;
+    let altitudes = elevation_data.processed_altitude();
+    let total_m = distances.last().copied().unwrap_or(0.0);
+    let step_m = interval * unit.metres();
+    if step_m <= 0.0 {
+        return Vec::new();
+    }
+
+    (1..)
+        .map(|i| i as f64 * interval)
+        .take_while(|distance| distance * unit.metres() < total_m)
+        .map(|distance| {
+            let distance_m = distance * unit.metres();
+            let point = point_at(gpx_data, distances, distance_m);
+            DistanceMarker {
+                name: format!("{} {}", format_distance(distance), unit.as_str()),
+                distance,
+                distance_m,
+                lat: point.lat,
+                lon: point.lon,
+                ele: interpolate(distances, &altitudes, distance_m),
+            }
+        })
+        .collect()
+}
+
+/// "5" rather than "5.0", but keep fractional intervals such as "2.5"
+fn format_distance(distance: f64) -> String {
+    let rounded = (distance * 1000.0).round() / 1000.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Markers as GPX waypoints, for watch course points and mapping tools
+pub fn markers_to_gpx(markers: &[DistanceMarker], name: &str) -> String {
+    let mut gpx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gpx.push_str("<gpx version=\"1.1\" creator=\"Volt\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+    gpx.push_str(&format!("  <metadata><name>{}</name></metadata>\n", escape_xml(name)));
+
+    for marker in markers {
+        gpx.push_str(&format!(
+            "  <wpt lat=\"{:.7}\" lon=\"{:.7}\"><ele>{:.1}</ele><name>{}</name><sym>Flag</sym><type>distance</type></wpt>\n",
+            marker.lat,
+            marker.lon,
+            marker.ele,
+            escape_xml(&marker.name),
+        ));
+    }
+
+    gpx.push_str("</gpx>\n");
+    gpx
+}
+
+/// Markers as a GeoJSON FeatureCollection of points
+pub fn markers_to_geojson(markers: &[DistanceMarker], unit: SplitUnit) -> serde_json::Value {
+    let features: Vec<serde_json::Value> = markers.iter()
+        .map(|marker| serde_json::json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": [marker.lon, marker.lat, marker.ele],
+            },
+            "properties": {
+                "name": marker.name,
+                "distance": marker.distance,
+                "unit": unit.as_str(),
+                "distance_m": marker.distance_m,
+                "ele": marker.ele,
+            },
+        }))
+        .collect();
+
+    serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    #[test]
+    fn test_markers_every_interval() {
+        // ~10 km northwards, climbing 10 m per km
+        let (gpx_data, elevation_data) = straight_course(900, |d| d * 0.01);
+
+        let markers = distance_markers(&gpx_data, &elevation_data, 2.5, SplitUnit::Kilometre);
+        assert_eq!(markers.len(), 3);
+        assert_eq!(markers[0].name, "2.5 km");
+        assert_eq!(markers[1].name, "5 km");
+        assert!((markers[1].ele - 50.0).abs() < 0.5);
+        assert!((markers[1].lat - (46.0 + 5000.0 / 111_195.0)).abs() < 1e-4);
+
+        let miles = distance_markers(&gpx_data, &elevation_data, 1.0, SplitUnit::Mile);
+        assert_eq!(miles.len(), 6);
+
+        let gpx = markers_to_gpx(&markers, "Race & Co");
+        assert_eq!(gpx.matches("<wpt").count(), 3);
+        assert!(gpx.contains("Race &amp; Co"));
+
+        let geojson = markers_to_geojson(&markers, SplitUnit::Kilometre);
+        assert_eq!(geojson["features"].as_array().unwrap().len(), 3);
+        assert_eq!(geojson["features"][0]["geometry"]["type"], "Point");
+    }
+}
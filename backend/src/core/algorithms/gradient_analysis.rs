@@ -1,4 +1,6 @@
-use crate::core::models::race::GpxData;
+use serde::{Deserialize, Serialize};
+
+use crate::core::services::elevation_processor::ElevationData;
 
 #[derive(Debug, Clone)]
 pub struct GradientSegment {
@@ -9,23 +11,20 @@ pub struct GradientSegment {
     pub gradient_percent: f64,
 }
 
-pub fn analyze_gradients(gpx_data: &GpxData, window_size_m: f64) -> Vec<GradientSegment> {
+/// Split processed elevation data into windows of at least `window_size_m`
+/// and measure the gradient over each, so smoothing and capping carry through
+pub fn analyze_gradients(elevation_data: &ElevationData, window_size_m: f64) -> Vec<GradientSegment> {
     let mut segments = Vec::new();
     let mut current_distance = 0.0;
+    let mut elevation_change = 0.0;
     let mut window_start_idx = 0;
     
-    for i in 1..gpx_data.points.len() {
-        let prev = &gpx_data.points[i - 1];
-        let curr = &gpx_data.points[i];
-        
-        let segment_distance = haversine_distance(prev.lat, prev.lon, curr.lat, curr.lon) * 1000.0; // to meters
-        current_distance += segment_distance;
+    for i in 1..elevation_data.distance_change.len() {
+        current_distance += elevation_data.distance_change[i];
+        elevation_change += elevation_data.altitude_change[i];
         
         // Check if we've reached the window size
         if current_distance >= window_size_m {
-            let start_ele = gpx_data.points[window_start_idx].ele;
-            let end_ele = curr.ele;
-            let elevation_change = end_ele - start_ele;
             let gradient = (elevation_change / current_distance) * 100.0;
             
             segments.push(GradientSegment {
@@ -39,84 +38,161 @@ pub fn analyze_gradients(gpx_data: &GpxData, window_size_m: f64) -> Vec<Gradient
             // Move window
             window_start_idx = i;
             current_distance = 0.0;
+            elevation_change = 0.0;
         }
     }
     
     segments
 }
 
-pub fn categorize_gradients(segments: &[GradientSegment]) -> (Vec<(String, f64)>, Vec<(String, f64)>) {
-    let gradient_bins = vec![
-        ("0-5", 0.0, 5.0),
-        ("5-10", 5.0, 10.0),
-        ("10-15", 10.0, 15.0),
-        ("15-20", 15.0, 20.0),
-        ("20-25", 20.0, 25.0),
-        ("25-30", 25.0, 30.0),
-        ("30+", 30.0, f64::INFINITY),
-    ];
-    
-    let mut ascent_distances = vec![0.0; gradient_bins.len()];
-    let mut descent_distances = vec![0.0; gradient_bins.len()];
-    let mut total_ascent_distance = 0.0;
-    let mut total_descent_distance = 0.0;
-    
+/// Steepest gradient over any `window_m` stretch, or the whole profile when
+/// it is shorter than that
+pub fn max_gradient(distances: &[f64], altitudes: &[f64], window_m: f64) -> f64 {
+    let total = distances[distances.len() - 1] - distances[0];
+    if total <= 0.0 {
+        return 0.0;
+    }
+    if total <= window_m {
+        return (altitudes[altitudes.len() - 1] - altitudes[0]) / total * 100.0;
+    }
+
+    let mut start = 0;
+    let mut steepest = f64::NEG_INFINITY;
+
+    for end in 1..distances.len() {
+        while distances[end] - distances[start + 1] >= window_m {
+            start += 1;
+        }
+        let span = distances[end] - distances[start];
+        if span >= window_m {
+            steepest = steepest.max((altitudes[end] - altitudes[start]) / span * 100.0);
+        }
+    }
+
+    steepest
+}
+
+/// Gradient bin edges in percent, applied to ascents and descents alike
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradientBins {
+    /// Ascending lower edges; the last bin is open-ended
+    pub edges: Vec<f64>,
+    /// Segments with an absolute gradient at or below this count as flat
+    pub flat_threshold: f64,
+}
+
+impl Default for GradientBins {
+    fn default() -> Self {
+        Self {
+            edges: vec![0.0, 5.0, 10.0, 15.0, 20.0, 25.0, 30.0],
+            flat_threshold: 0.0,
+        }
+    }
+}
+
+impl GradientBins {
+    /// Validate custom edges, adding a 0% edge if the caller left it out
+    pub fn new(mut edges: Vec<f64>, flat_threshold: f64) -> Result<Self, String> {
+        if edges.iter().any(|edge| !edge.is_finite() || *edge < 0.0) {
+            return Err("Gradient bin edges must be non-negative numbers".to_string());
+        }
+        if edges.windows(2).any(|pair| pair[1] <= pair[0]) {
+            return Err("Gradient bin edges must be strictly increasing".to_string());
+        }
+        if !flat_threshold.is_finite() || flat_threshold < 0.0 {
+            return Err("Flat threshold must be a non-negative number".to_string());
+        }
+        if edges.first() != Some(&0.0) {
+            edges.insert(0, 0.0);
+        }
+
+        Ok(Self { edges, flat_threshold })
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        self.edges.iter()
+            .enumerate()
+            .map(|(i, edge)| match self.edges.get(i + 1) {
+                Some(next) => format!("{}-{}", edge, next),
+                None => format!("{}+", edge),
+            })
+            .collect()
+    }
+
+    fn index(&self, abs_gradient: f64) -> usize {
+        self.edges.partition_point(|edge| *edge <= abs_gradient).saturating_sub(1)
+    }
+}
+
+/// Distance in metres falling into each bin, per direction
+#[derive(Debug, Clone)]
+pub struct GradientCategories {
+    pub labels: Vec<String>,
+    pub ascent_m: Vec<f64>,
+    pub descent_m: Vec<f64>,
+    pub flat_m: f64,
+}
+
+pub fn categorize_gradients(segments: &[GradientSegment], bins: &GradientBins) -> GradientCategories {
+    let mut categories = GradientCategories {
+        labels: bins.labels(),
+        ascent_m: vec![0.0; bins.edges.len()],
+        descent_m: vec![0.0; bins.edges.len()],
+        flat_m: 0.0,
+    };
+
     for segment in segments {
         let abs_gradient = segment.gradient_percent.abs();
-        
-        for (i, (_, min, max)) in gradient_bins.iter().enumerate() {
-            if abs_gradient >= *min && abs_gradient < *max {
-                if segment.gradient_percent > 0.0 {
-                    ascent_distances[i] += segment.distance_m;
-                    total_ascent_distance += segment.distance_m;
-                } else {
-                    descent_distances[i] += segment.distance_m;
-                    total_descent_distance += segment.distance_m;
-                }
-                break;
-            }
+
+        if abs_gradient <= bins.flat_threshold {
+            categories.flat_m += segment.distance_m;
+        } else if segment.gradient_percent > 0.0 {
+            categories.ascent_m[bins.index(abs_gradient)] += segment.distance_m;
+        } else {
+            categories.descent_m[bins.index(abs_gradient)] += segment.distance_m;
         }
     }
-    
-    // Convert to percentages
-    let ascent_percentages: Vec<(String, f64)> = gradient_bins
-        .iter()
-        .zip(ascent_distances.iter())
-        .map(|((name, _, _), dist)| {
-            let percentage = if total_ascent_distance > 0.0 {
-                (dist / total_ascent_distance) * 100.0
-            } else {
-                0.0
-            };
-            (name.to_string(), percentage)
-        })
-        .collect();
-    
-    let descent_percentages: Vec<(String, f64)> = gradient_bins
-        .iter()
-        .zip(descent_distances.iter())
-        .map(|((name, _, _), dist)| {
-            let percentage = if total_descent_distance > 0.0 {
-                (dist / total_descent_distance) * 100.0
-            } else {
-                0.0
-            };
-            (name.to_string(), percentage)
-        })
-        .collect();
-    
-    (ascent_percentages, descent_percentages)
+
+    categories
 }
 
-fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    let r = 6371.0; // Earth radius in km
-    let dlat = (lat2 - lat1).to_radians();
-    let dlon = (lon2 - lon1).to_radians();
-    let lat1 = lat1.to_radians();
-    let lat2 = lat2.to_radians();
-    
-    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
-    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
-    
-    r * c
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(distance_m: f64, gradient_percent: f64) -> GradientSegment {
+        GradientSegment {
+            start_idx: 0,
+            end_idx: 1,
+            distance_m,
+            elevation_change_m: distance_m * gradient_percent / 100.0,
+            gradient_percent,
+        }
+    }
+
+    #[test]
+    fn test_custom_bins_with_flat_band() {
+        let bins = GradientBins::new(vec![3.0, 8.0], 1.0).unwrap();
+        assert_eq!(bins.labels(), vec!["0-3", "3-8", "8+"]);
+
+        let segments = vec![
+            segment(100.0, 0.0),
+            segment(100.0, -0.5),
+            segment(100.0, 2.0),
+            segment(100.0, 8.0),
+            segment(100.0, -12.0),
+        ];
+        let categories = categorize_gradients(&segments, &bins);
+
+        // Level ground is flat, not descent
+        assert_eq!(categories.flat_m, 200.0);
+        assert_eq!(categories.ascent_m, vec![100.0, 0.0, 100.0]);
+        assert_eq!(categories.descent_m, vec![0.0, 0.0, 100.0]);
+    }
+
+    #[test]
+    fn test_invalid_bins() {
+        assert!(GradientBins::new(vec![5.0, 5.0], 0.0).is_err());
+        assert!(GradientBins::new(vec![-1.0, 5.0], 0.0).is_err());
+    }
 }
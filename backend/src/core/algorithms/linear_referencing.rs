@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::models::race::{GpxData, GpxPoint};
+
+/// Where a point sits along a track
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TrackPosition {
+    /// Distance from the start in metres
+    pub distance_m: f64,
+    /// Closest point on the track
+    pub lat: f64,
+    pub lon: f64,
+    pub ele: f64,
+    /// How far the original point was from the track, in metres
+    pub offset_m: f64,
+}
+
+/// Where a coordinate falls on a course that may pass near it more than once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocatedPoint {
+    pub nearest: Option<TrackPosition>,
+    /// Closest point of every separate pass within the search radius, in track order
+    pub passes: Vec<TrackPosition>,
+}
+
+/// Cumulative distance in metres at every track point
+pub fn cumulative_distances(gpx_data: &GpxData) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut distances = Vec::with_capacity(gpx_data.points.len());
+
+    for (i, point) in gpx_data.points.iter().enumerate() {
+        if i > 0 {
+            total += distance_m(&gpx_data.points[i - 1], point);
+        }
+        distances.push(total);
+    }
+
+    distances
+}
+
+/// Project a coordinate onto the closest segment of the track
+pub fn snap_to_track(gpx_data: &GpxData, distances: &[f64], lat: f64, lon: f64) -> Option<TrackPosition> {
+    snap_candidates(&gpx_data.points, distances, lat, lon)
+        .min_by(|a, b| a.offset_m.total_cmp(&b.offset_m))
+}
+
+/// Snap a coordinate to each time the track comes within `max_offset_m` of it.
+///
+/// Consecutive segments within the radius belong to the same pass, so a
+/// course through the same spot twice (a lap, an out-and-back) gives two.
+pub fn locate_on_track(gpx_data: &GpxData, distances: &[f64], lat: f64, lon: f64, max_offset_m: f64) -> LocatedPoint {
+    let mut nearest: Option<TrackPosition> = None;
+    let mut passes: Vec<TrackPosition> = Vec::new();
+    let mut in_pass = false;
+
+    for candidate in snap_candidates(&gpx_data.points, distances, lat, lon) {
+        if nearest.as_ref().is_none_or(|n| candidate.offset_m < n.offset_m) {
+            nearest = Some(candidate);
+        }
+
+        if candidate.offset_m > max_offset_m {
+            in_pass = false;
+            continue;
+        }
+        match passes.last_mut() {
+            Some(best) if in_pass => {
+                if candidate.offset_m < best.offset_m {
+                    *best = candidate;
+                }
+            }
+            _ => passes.push(candidate),
+        }
+        in_pass = true;
+    }
+
+    LocatedPoint { nearest, passes }
+}
+
+/// Projection of a coordinate onto every segment of the track, in track order
+pub fn snap_candidates<'a>(
+    points: &'a [GpxPoint],
+    distances: &'a [f64],
+    lat: f64,
+    lon: f64,
+) -> impl Iterator<Item = TrackPosition> + 'a {
+    let single = (points.len() == 1).then(|| {
+        let point = &points[0];
+        TrackPosition {
+            distance_m: 0.0,
+            lat: point.lat,
+            lon: point.lon,
+            ele: point.ele,
+            offset_m: haversine_distance(lat, lon, point.lat, point.lon) * 1000.0,
+        }
+    });
+
+    points.windows(2)
+        .enumerate()
+        .map(move |(i, pair)| {
+            let t = project(&pair[0], &pair[1], lat, lon);
+            let point = lerp_point(&pair[0], &pair[1], t);
+
+            TrackPosition {
+                distance_m: distances[i] + t * (distances[i + 1] - distances[i]),
+                lat: point.lat,
+                lon: point.lon,
+                ele: point.ele,
+                offset_m: haversine_distance(lat, lon, point.lat, point.lon) * 1000.0,
+            }
+        })
+        .chain(single)
+}
+
+/// Interpolated track point at `distance_m` from the start, clamped to the ends
+pub fn point_at(gpx_data: &GpxData, distances: &[f64], distance_m: f64) -> GpxPoint {
+    let points = &gpx_data.points;
+    match distances.partition_point(|d| *d < distance_m) {
+        0 => strip_time(&points[0]),
+        i if i >= distances.len() => strip_time(&points[points.len() - 1]),
+        i => {
+            let span = distances[i] - distances[i - 1];
+            let t = if span > 0.0 { (distance_m - distances[i - 1]) / span } else { 1.0 };
+            lerp_point(&points[i - 1], &points[i], t)
+        }
+    }
+}
+
+/// The part of the track between two distances, with interpolated end points
+pub fn slice_track(gpx_data: &GpxData, distances: &[f64], from_m: f64, to_m: f64) -> GpxData {
+    let mut points = vec![point_at(gpx_data, distances, from_m)];
+
+    points.extend(
+        gpx_data.points.iter()
+            .zip(distances)
+            .filter(|(_, d)| **d > from_m && **d < to_m)
+            .map(|(point, _)| point.clone()),
+    );
+
+    if to_m > from_m {
+        points.push(point_at(gpx_data, distances, to_m));
+    }
+
+    GpxData { points }
+}
+
+/// Fraction along a→b of the point closest to (lat, lon), on a local flat projection
+fn project(a: &GpxPoint, b: &GpxPoint, lat: f64, lon: f64) -> f64 {
+    let cos_lat = a.lat.to_radians().cos();
+    let (dx, dy) = ((b.lon - a.lon) * cos_lat, b.lat - a.lat);
+    let (px, py) = ((lon - a.lon) * cos_lat, lat - a.lat);
+    let length_sq = dx * dx + dy * dy;
+
+    if length_sq == 0.0 {
+        return 0.0;
+    }
+    ((px * dx + py * dy) / length_sq).clamp(0.0, 1.0)
+}
+
+fn lerp_point(a: &GpxPoint, b: &GpxPoint, t: f64) -> GpxPoint {
+    GpxPoint {
+        lat: a.lat + t * (b.lat - a.lat),
+        lon: a.lon + t * (b.lon - a.lon),
+        ele: a.ele + t * (b.ele - a.ele),
+        time: None,
+    }
+}
+
+fn strip_time(point: &GpxPoint) -> GpxPoint {
+    GpxPoint { time: None, ..point.clone() }
+}
+
+/// Great-circle distance between two track points, in metres
+pub(crate) fn distance_m(a: &GpxPoint, b: &GpxPoint) -> f64 {
+    haversine_distance(a.lat, a.lon, b.lat, b.lon) * 1000.0
+}
+
+fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let r = 6371.0; // Earth radius in km
+    let dlat = (lat2 - lat1).to_radians();
+    let dlon = (lon2 - lon1).to_radians();
+    let lat1 = lat1.to_radians();
+    let lat2 = lat2.to_radians();
+
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+
+    r * c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn straight_track() -> GpxData {
+        GpxData {
+            points: (0..=10)
+                .map(|i| GpxPoint { lat: 46.0 + i as f64 * 0.001, lon: 7.0, ele: i as f64 * 10.0, time: None })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_snap_to_track() {
+        let track = straight_track();
+        let distances = cumulative_distances(&track);
+
+        // 50 m east of the track, a third of the way along the fourth segment
+        let position = snap_to_track(&track, &distances, 46.00333, 7.00065).unwrap();
+
+        assert!((position.distance_m - distances[3] - 37.0).abs() < 1.0);
+        assert!((position.offset_m - 50.0).abs() < 1.0);
+        assert!((position.ele - 33.3).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_locate_on_out_and_back() {
+        // Out along the straight track and back the same way
+        let mut track = straight_track();
+        let back: Vec<GpxPoint> = track.points.iter().rev().skip(1).cloned().collect();
+        track.points.extend(back);
+        let distances = cumulative_distances(&track);
+
+        let located = locate_on_track(&track, &distances, 46.00333, 7.00065, 100.0);
+
+        assert_eq!(located.passes.len(), 2);
+        assert!((located.passes[0].distance_m - distances[3] - 37.0).abs() < 1.0);
+        assert!((located.passes[1].distance_m - (distances[20] - distances[3] - 37.0)).abs() < 1.0);
+        assert!((located.nearest.unwrap().offset_m - 50.0).abs() < 1.0);
+
+        assert!(locate_on_track(&track, &distances, 46.00333, 7.01, 100.0).passes.is_empty());
+    }
+
+    #[test]
+    fn test_slice_track() {
+        let track = straight_track();
+        let distances = cumulative_distances(&track);
+
+        let slice = slice_track(&track, &distances, 150.0, 420.0);
+        let slice_distances = cumulative_distances(&slice);
+
+        assert_eq!(slice.points.len(), 4);
+        assert!((slice_distances.last().unwrap() - 270.0).abs() < 0.5);
+    }
+}
@@ -1,2 +1,14 @@
 pub mod gradient_analysis;
 pub mod route_matching;
+pub mod smoothing;
+pub mod resampling;
+pub mod climb_detection;
+pub mod steepest_sections;
+pub mod splits;
+pub mod linear_referencing;
+pub mod terrain_classification;
+pub mod altitude_exposure;
+pub mod route_topology;
+pub mod distance_markers;
+pub mod track_repair;
+pub mod consensus_track;
@@ -0,0 +1,158 @@
+/// Elevation profile sampled at a fixed distance interval
+#[derive(Debug, Clone, Default)]
+pub struct ResampledProfile {
+    pub distance_m: Vec<f64>,
+    pub elevation: Vec<f64>,
+    /// Extremes of the original points in each sample's bucket, so narrow peaks survive
+    pub min_elevation: Vec<f64>,
+    pub max_elevation: Vec<f64>,
+}
+
+/// Resample a profile every `spacing_m` metres between `from_m` and `to_m`.
+///
+/// Each sample's elevation is interpolated at its distance; its bucket spans
+/// half a spacing either side and records the min and max of the original points.
+pub fn resample_profile(
+    distances: &[f64],
+    elevations: &[f64],
+    spacing_m: f64,
+    from_m: f64,
+    to_m: f64,
+) -> ResampledProfile {
+    let mut profile = ResampledProfile::default();
+
+    if distances.is_empty() || spacing_m <= 0.0 || to_m < from_m {
+        return profile;
+    }
+
+    let count = ((to_m - from_m) / spacing_m).floor() as usize + 1;
+    let mut cursor = 0;
+
+    let bucket = |distance: f64| {
+        (
+            (distance - spacing_m / 2.0).max(from_m),
+            (distance + spacing_m / 2.0).min(to_m),
+        )
+    };
+
+    for k in 0..count {
+        let distance = (from_m + k as f64 * spacing_m).min(to_m);
+        push_bucket(&mut profile, distances, elevations, distance, bucket(distance), &mut cursor);
+    }
+
+    // Always finish exactly at the end of the range
+    if profile.distance_m.last().map(|d| to_m - d > 1e-6).unwrap_or(false) {
+        push_bucket(&mut profile, distances, elevations, to_m, bucket(to_m), &mut cursor);
+    }
+
+    profile
+}
+
+/// Every original point between `from_m` and `to_m`, with interpolated end points
+pub fn slice_profile(distances: &[f64], elevations: &[f64], from_m: f64, to_m: f64) -> ResampledProfile {
+    let mut profile = ResampledProfile::default();
+
+    if distances.is_empty() || to_m < from_m {
+        return profile;
+    }
+
+    let mut push = |distance: f64, elevation: f64| {
+        profile.distance_m.push(distance);
+        profile.elevation.push(elevation);
+        profile.min_elevation.push(elevation);
+        profile.max_elevation.push(elevation);
+    };
+
+    push(from_m, interpolate(distances, elevations, from_m));
+    for (&distance, &elevation) in distances.iter().zip(elevations) {
+        if distance > from_m && distance < to_m {
+            push(distance, elevation);
+        }
+    }
+    if to_m > from_m {
+        push(to_m, interpolate(distances, elevations, to_m));
+    }
+
+    profile
+}
+
+/// Linear interpolation of the profile at `distance`, clamped to its ends
+pub fn interpolate(distances: &[f64], elevations: &[f64], distance: f64) -> f64 {
+    match distances.partition_point(|d| *d < distance) {
+        0 => elevations[0],
+        i if i >= distances.len() => elevations[distances.len() - 1],
+        i => {
+            let span = distances[i] - distances[i - 1];
+            if span <= 0.0 {
+                return elevations[i];
+            }
+            let t = (distance - distances[i - 1]) / span;
+            elevations[i - 1] + t * (elevations[i] - elevations[i - 1])
+        }
+    }
+}
+
+fn push_bucket(
+    profile: &mut ResampledProfile,
+    distances: &[f64],
+    elevations: &[f64],
+    distance: f64,
+    (bucket_start, bucket_end): (f64, f64),
+    cursor: &mut usize,
+) {
+    let elevation = interpolate(distances, elevations, distance);
+
+    let mut min_elevation = elevation;
+    let mut max_elevation = elevation;
+
+    // Buckets advance monotonically, so the scan resumes where the last one started
+    while *cursor < distances.len() && distances[*cursor] < bucket_start {
+        *cursor += 1;
+    }
+    let mut i = *cursor;
+    while i < distances.len() && distances[i] < bucket_end {
+        min_elevation = min_elevation.min(elevations[i]);
+        max_elevation = max_elevation.max(elevations[i]);
+        i += 1;
+    }
+
+    profile.distance_m.push(distance);
+    profile.elevation.push(elevation);
+    profile.min_elevation.push(min_elevation);
+    profile.max_elevation.push(max_elevation);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resampling_keeps_peaks() {
+        // 10 m spacing with a single-point spike at 455 m
+        let distances: Vec<f64> = (0..=100).map(|i| i as f64 * 10.0).collect();
+        let mut elevations = vec![100.0; distances.len()];
+        elevations[45] = 180.0;
+        let distances_with_spike: Vec<f64> = distances.iter()
+            .map(|d| if *d == 450.0 { 455.0 } else { *d })
+            .collect();
+
+        let profile = resample_profile(&distances_with_spike, &elevations, 100.0, 0.0, 1000.0);
+
+        assert_eq!(profile.distance_m.len(), 11);
+        assert_eq!(profile.distance_m[10], 1000.0);
+        // The spike falls between samples but survives in the bucket maximum
+        assert!(profile.elevation.iter().all(|e| *e < 180.0));
+        assert_eq!(profile.max_elevation[5], 180.0);
+    }
+
+    #[test]
+    fn test_slice_interpolates_ends() {
+        let distances = vec![0.0, 100.0, 200.0, 300.0];
+        let elevations = vec![0.0, 10.0, 20.0, 30.0];
+
+        let profile = slice_profile(&distances, &elevations, 50.0, 250.0);
+
+        assert_eq!(profile.distance_m, vec![50.0, 100.0, 200.0, 250.0]);
+        assert_eq!(profile.elevation, vec![5.0, 10.0, 20.0, 25.0]);
+    }
+}
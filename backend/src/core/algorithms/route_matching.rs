@@ -1,11 +1,23 @@
+use crate::core::algorithms::climb_detection::{climb_similarity, detect_climbs, Climb, ClimbDetectionConfig};
+use crate::core::algorithms::route_topology::{detect_topology, RouteTopology};
+use crate::core::algorithms::terrain_classification::TerrainClass;
+use crate::core::models::processing::ProcessingOptions;
 use crate::core::models::synthesis::{BoundingBox, RouteCandidate};
-use crate::core::models::race::GpxData;
-use crate::core::services::itra_calculator::calculate_similarity;
+use crate::core::models::race::{GpxData, GpxPoint};
+use crate::core::services::elevation_processor::ElevationData;
+use crate::core::services::effort_model::{effort_similarity, EffortModelKind};
 
 pub struct RouteMatchingConfig {
     pub max_results: usize,
     pub min_similarity_score: f64,
     pub distance_tolerance: f64, // percentage
+    /// Share of the score given to climb structure rather than effort distance
+    pub climb_weight: f64,
+    /// Share of the score given to matching the course shape
+    pub topology_weight: f64,
+    pub effort_model: EffortModelKind,
+    /// Terrain classes a candidate's stored terrain may have; empty allows any
+    pub terrain: Vec<TerrainClass>,
 }
 
 impl Default for RouteMatchingConfig {
@@ -14,28 +26,62 @@ impl Default for RouteMatchingConfig {
             max_results: 50,
             min_similarity_score: 0.5,
             distance_tolerance: 0.2, // 20% tolerance
+            climb_weight: 0.3,
+            topology_weight: 0.2,
+            effort_model: EffortModelKind::Itra,
+            terrain: Vec::new(),
         }
     }
 }
 
 pub fn find_similar_routes(
-    _reference: &GpxData,  // Prefix with underscore to indicate it's intentionally unused
-    reference_metrics: (f64, f64, f64), // distance, gain, loss
+    reference: &GpxData,
     candidates: Vec<RouteCandidate>,
     config: RouteMatchingConfig,
 ) -> Vec<RouteCandidate> {
-    let (ref_distance, ref_gain, _) = reference_metrics;
+    let model = config.effort_model.model();
+    let reference_data = ElevationData::from_gpx_data(reference, &ProcessingOptions::default());
+    let reference_effort = model.estimate(&reference_data).effort_distance_km;
+    let reference_km = reference_data.cumulative_distance.last().copied().unwrap_or(0.0) / 1000.0;
+    let reference_climbs = route_climbs(&reference_data);
+    let reference_topology = detect_topology(reference);
     
     let mut scored_candidates: Vec<(f64, RouteCandidate)> = candidates
         .into_iter()
-        .map(|candidate| {
-            let similarity = calculate_similarity(
-                ref_distance,
-                ref_gain,
-                candidate.distance_km,
-                candidate.elevation_gain_m,
+        .filter(|candidate| {
+            reference_km > 0.0
+                && (candidate.distance_km - reference_km).abs() / reference_km <= config.distance_tolerance
+        })
+        // Classified with the candidate's own sport profile, as in the library
+        .filter(|candidate| {
+            config.terrain.is_empty()
+                || config.terrain.iter().any(|terrain| candidate.terrain.as_deref() == Some(terrain.as_str()))
+        })
+        .filter_map(|candidate| {
+            let candidate_gpx = GpxData {
+                points: candidate.route.points.iter()
+                    .map(|p| GpxPoint { lat: p.lat, lon: p.lon, ele: p.ele, time: None })
+                    .collect(),
+            };
+            let candidate_data = ElevationData::from_gpx_data(&candidate_gpx, &ProcessingOptions::default());
+            // Lap races are trained for on loops
+            let candidate_topology = detect_topology(&candidate_gpx).topology;
+            if reference_topology.laps > 1 && candidate_topology != RouteTopology::Loop {
+                return None;
+            }
+            
+            let effort_score = effort_similarity(
+                reference_effort,
+                model.estimate(&candidate_data).effort_distance_km,
             );
-            (similarity, candidate)
+            let structure_score = climb_similarity(&reference_climbs, &route_climbs(&candidate_data));
+            
+            let topology_score = topology_similarity(reference_topology.topology, candidate_topology);
+            
+            let similarity = (1.0 - config.climb_weight - config.topology_weight) * effort_score
+                + config.climb_weight * structure_score
+                + config.topology_weight * topology_score;
+            Some((similarity, candidate))
         })
         .filter(|(score, _)| *score >= config.min_similarity_score)
         .collect();
@@ -54,6 +100,21 @@ pub fn find_similar_routes(
         .collect()
 }
 
+/// 1 for the same shape, 0.5 when both finish where they start
+fn topology_similarity(reference: RouteTopology, candidate: RouteTopology) -> f64 {
+    if reference == candidate {
+        1.0
+    } else if reference.is_closed() && candidate.is_closed() {
+        0.5
+    } else {
+        0.0
+    }
+}
+
+fn route_climbs(elevation_data: &ElevationData) -> Vec<Climb> {
+    detect_climbs(elevation_data, &ClimbDetectionConfig::default())
+}
+
 pub fn filter_routes_by_bbox(
     routes: Vec<RouteCandidate>,
     bbox: &BoundingBox,
@@ -70,7 +131,7 @@ pub fn filter_routes_by_bbox(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::models::synthesis::RouteData;
+    use crate::core::models::synthesis::{RouteData, RoutePoint};
     
     #[test]
     fn test_route_filtering() {
@@ -87,6 +148,7 @@ mod tests {
             elevation_gain_m: 2000.0,
             elevation_loss_m: 2000.0,
             itra_effort_distance: 70.0,
+            terrain: None,
             similarity_score: 0.0,
             route: RouteData {
                 points: vec![
@@ -101,6 +163,7 @@ mod tests {
             elevation_gain_m: 2000.0,
             elevation_loss_m: 2000.0,
             itra_effort_distance: 70.0,
+            terrain: None,
             similarity_score: 0.0,
             route: RouteData {
                 points: vec![
@@ -113,4 +176,87 @@ mod tests {
         assert_eq!(filtered.len(), 1);
         assert_eq!(filtered[0].id, "1");
     }
+
+    /// Flat route through `corners` (x, y in metres), with a point every ~10 m
+    fn route(corners: &[(f64, f64)]) -> Vec<RoutePoint> {
+        let to_point = |(x, y): (f64, f64)| RoutePoint {
+            lat: 46.0 + y / 110_540.0,
+            lon: 7.0 + x / (111_320.0 * 46f64.to_radians().cos()),
+            ele: 500.0,
+        };
+        let mut points = Vec::new();
+        for pair in corners.windows(2) {
+            let steps = ((pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1) / 10.0).ceil() as usize;
+            for s in 0..steps {
+                let t = s as f64 / steps as f64;
+                points.push(to_point((pair[0].0 + t * (pair[1].0 - pair[0].0), pair[0].1 + t * (pair[1].1 - pair[0].1))));
+            }
+        }
+        points.push(to_point(*corners.last().unwrap()));
+        points
+    }
+
+    fn candidate(id: &str, corners: &[(f64, f64)]) -> RouteCandidate {
+        RouteCandidate {
+            id: id.to_string(),
+            distance_km: 12.0,
+            elevation_gain_m: 0.0,
+            elevation_loss_m: 0.0,
+            itra_effort_distance: 12.0,
+            terrain: None,
+            similarity_score: 0.0,
+            route: RouteData { points: route(corners) },
+        }
+    }
+
+    #[test]
+    fn test_lap_race_gets_loops() {
+        // Three laps of a 4 km square
+        let square = [(0.0, 0.0), (1000.0, 0.0), (1000.0, 1000.0), (0.0, 1000.0), (0.0, 0.0)];
+        let laps: Vec<(f64, f64)> = square.iter().copied()
+            .chain(square[1..].iter().copied())
+            .chain(square[1..].iter().copied())
+            .collect();
+        let reference = GpxData {
+            points: route(&laps).into_iter()
+                .map(|p| GpxPoint { lat: p.lat, lon: p.lon, ele: p.ele, time: None })
+                .collect(),
+        };
+
+        let candidates = vec![
+            candidate("loop", &[(0.0, 0.0), (3000.0, 0.0), (3000.0, 3000.0), (0.0, 3000.0), (0.0, 0.0)]),
+            candidate("out_and_back", &[(0.0, 0.0), (6000.0, 0.0), (0.0, 0.0)]),
+        ];
+        let results = find_similar_routes(&reference, candidates, RouteMatchingConfig::default());
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "loop");
+        assert!(results[0].similarity_score > 0.9);
+    }
+
+    #[test]
+    fn test_terrain_filter_uses_stored_class() {
+        // Both flat on the ground; only the stored class tells them apart
+        let square = [(0.0, 0.0), (3000.0, 0.0), (3000.0, 3000.0), (0.0, 3000.0), (0.0, 0.0)];
+        let reference = GpxData {
+            points: route(&square).into_iter()
+                .map(|p| GpxPoint { lat: p.lat, lon: p.lon, ele: p.ele, time: None })
+                .collect(),
+        };
+        let stored = |id: &str, terrain: Option<TerrainClass>| RouteCandidate {
+            terrain: terrain.map(|t| t.as_str().to_string()),
+            ..candidate(id, &square)
+        };
+
+        let candidates = vec![
+            stored("hilly", Some(TerrainClass::Hilly)),
+            stored("flat", Some(TerrainClass::Flat)),
+            stored("unclassified", None),
+        ];
+        let config = RouteMatchingConfig { terrain: vec![TerrainClass::Hilly], ..Default::default() };
+        let results = find_similar_routes(&reference, candidates, config);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "hilly");
+    }
 }
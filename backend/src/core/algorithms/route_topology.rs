@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::core::algorithms::linear_referencing::{cumulative_distances, point_at};
+use crate::core::models::race::GpxData;
+
+/// Track points further apart than this are never the same place, in metres
+const MATCH_RADIUS_M: f64 = 30.0;
+
+/// Matches closer than this along the track are just neighbouring points
+const MIN_GAP_M: f64 = 300.0;
+
+/// Most samples compared; long courses are sampled more coarsely
+const MAX_SAMPLES: f64 = 3000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteTopology {
+    Loop,
+    OutAndBack,
+    PointToPoint,
+    /// Out along a stem, round a loop and back along the stem
+    Lollipop,
+}
+
+impl RouteTopology {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteTopology::Loop => "loop",
+            RouteTopology::OutAndBack => "out_and_back",
+            RouteTopology::PointToPoint => "point_to_point",
+            RouteTopology::Lollipop => "lollipop",
+        }
+    }
+
+    /// Whether the course finishes where it started
+    pub fn is_closed(&self) -> bool {
+        !matches!(self, RouteTopology::PointToPoint)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyAnalysis {
+    pub topology: RouteTopology,
+    pub start_finish_distance_m: f64,
+    /// Share of the track that is also covered in the opposite direction
+    pub retraced_fraction: f64,
+    /// Share of the track that is covered again in the same direction
+    pub repeated_fraction: f64,
+    /// Times the same loop is run; 1 unless the course is a lap race
+    pub laps: usize,
+    pub lap_distance_km: Option<f64>,
+}
+
+/// Work out the shape of a course from its geometry
+pub fn detect_topology(gpx_data: &GpxData) -> TopologyAnalysis {
+    let distances = cumulative_distances(gpx_data);
+    let total_m = distances.last().copied().unwrap_or(0.0);
+
+    if gpx_data.points.len() < 2 || total_m <= 0.0 {
+        return TopologyAnalysis {
+            topology: RouteTopology::PointToPoint,
+            start_finish_distance_m: 0.0,
+            retraced_fraction: 0.0,
+            repeated_fraction: 0.0,
+            laps: 1,
+            lap_distance_km: None,
+        };
+    }
+
+    // Evenly spaced samples on a local metre grid around the start
+    let spacing = (total_m / MAX_SAMPLES).max(MATCH_RADIUS_M / 2.0);
+    let count = (total_m / spacing).ceil() as usize + 1;
+    let origin = &gpx_data.points[0];
+    let metres_per_lon = 111_320.0 * origin.lat.to_radians().cos();
+    let samples: Vec<(f64, f64)> = (0..count)
+        .map(|i| {
+            let point = point_at(gpx_data, &distances, (i as f64 * spacing).min(total_m));
+            ((point.lon - origin.lon) * metres_per_lon, (point.lat - origin.lat) * 110_540.0)
+        })
+        .collect();
+    let headings: Vec<(f64, f64)> = (0..count)
+        .map(|i| {
+            let (ax, ay) = samples[i.saturating_sub(1)];
+            let (bx, by) = samples[(i + 1).min(count - 1)];
+            let length = (bx - ax).hypot(by - ay);
+            if length > 0.0 { ((bx - ax) / length, (by - ay) / length) } else { (0.0, 0.0) }
+        })
+        .collect();
+
+    let distance = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).hypot(a.1 - b.1);
+    let dot = |a: (f64, f64), b: (f64, f64)| a.0 * b.0 + a.1 * b.1;
+
+    let start_finish_distance = distance(samples[0], samples[count - 1]);
+    let closed = start_finish_distance <= (0.02 * total_m).max(200.0);
+
+    // Bucket samples into cells one match radius wide to find revisits quickly
+    let cell = |(x, y): (f64, f64)| ((x / MATCH_RADIUS_M).floor() as i64, (y / MATCH_RADIUS_M).floor() as i64);
+    let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, sample) in samples.iter().enumerate() {
+        grid.entry(cell(*sample)).or_default().push(i);
+    }
+
+    let min_gap = (MIN_GAP_M / spacing).ceil() as usize;
+    let mut retraced = 0;
+    let mut repeated = 0;
+    for i in 0..count {
+        let (cx, cy) = cell(samples[i]);
+        let mut reverse = false;
+        let mut forward = false;
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for &j in grid.get(&(cx + dx, cy + dy)).into_iter().flatten() {
+                    if i.abs_diff(j) < min_gap || distance(samples[i], samples[j]) > MATCH_RADIUS_M {
+                        continue;
+                    }
+                    let alignment = dot(headings[i], headings[j]);
+                    reverse |= alignment < -0.5;
+                    forward |= alignment > 0.5;
+                }
+            }
+        }
+
+        retraced += reverse as usize;
+        repeated += forward as usize;
+    }
+    let retraced_fraction = retraced as f64 / count as f64;
+    let repeated_fraction = repeated as f64 / count as f64;
+
+    let topology = if !closed {
+        RouteTopology::PointToPoint
+    } else if retraced_fraction >= 0.7 {
+        RouteTopology::OutAndBack
+    } else if retraced_fraction >= 0.1 {
+        RouteTopology::Lollipop
+    } else {
+        RouteTopology::Loop
+    };
+
+    let lap_ends = if closed && repeated_fraction >= 0.3 {
+        lap_ends(&samples, &headings, min_gap)
+    } else {
+        Vec::new()
+    };
+    let lap_lengths: Vec<f64> = std::iter::once(0)
+        .chain(lap_ends.iter().copied())
+        .collect::<Vec<_>>()
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]) as f64 * spacing)
+        .collect();
+    // Laps have to be the same length, give or take 15%
+    let same_length = lap_lengths.first()
+        .is_some_and(|first| lap_lengths.iter().all(|length| (length - first).abs() <= 0.15 * first));
+    let (laps, lap_distance_km) = if lap_lengths.len() >= 2 && same_length {
+        (lap_lengths.len(), Some(total_m / lap_lengths.len() as f64 / 1000.0))
+    } else {
+        (1, None)
+    };
+
+    TopologyAnalysis {
+        topology,
+        start_finish_distance_m: start_finish_distance,
+        retraced_fraction,
+        repeated_fraction,
+        laps,
+        lap_distance_km,
+    }
+}
+
+/// Sample indices where the course comes back past the start heading the
+/// same way, plus the finish
+fn lap_ends(samples: &[(f64, f64)], headings: &[(f64, f64)], min_gap: usize) -> Vec<usize> {
+    let start = samples[0];
+    let radius = MATCH_RADIUS_M * 2.0;
+    let mut ends: Vec<usize> = Vec::new();
+    let mut last_near = 0;
+
+    for i in min_gap..samples.len() {
+        let near = (samples[i].0 - start.0).hypot(samples[i].1 - start.1) <= radius;
+        let same_way = headings[i].0 * headings[0].0 + headings[i].1 * headings[0].1 > 0.5;
+        if !near || !same_way {
+            continue;
+        }
+        // One pass can hit several samples within the radius
+        if i - last_near >= min_gap {
+            ends.push(i);
+        }
+        last_near = i;
+    }
+
+    let finish = samples.len() - 1;
+    match ends.last() {
+        Some(&last) if finish - last < min_gap => {}
+        _ => ends.push(finish),
+    }
+    ends
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::race::GpxPoint;
+
+    /// Track through `corners` (x, y in metres), with a point every ~10 m
+    fn track(corners: &[(f64, f64)]) -> GpxData {
+        let mut points = Vec::new();
+        for pair in corners.windows(2) {
+            let (ax, ay) = pair[0];
+            let (bx, by) = pair[1];
+            let steps = ((bx - ax).hypot(by - ay) / 10.0).ceil() as usize;
+            for s in 0..steps {
+                let t = s as f64 / steps as f64;
+                let (x, y) = (ax + t * (bx - ax), ay + t * (by - ay));
+                points.push(GpxPoint {
+                    lat: 46.0 + y / 110_540.0,
+                    lon: 7.0 + x / (111_320.0 * 46f64.to_radians().cos()),
+                    ele: 500.0,
+                    time: None,
+                });
+            }
+        }
+        let (x, y) = *corners.last().unwrap();
+        points.push(GpxPoint {
+            lat: 46.0 + y / 110_540.0,
+            lon: 7.0 + x / (111_320.0 * 46f64.to_radians().cos()),
+            ele: 500.0,
+            time: None,
+        });
+        GpxData { points }
+    }
+
+    #[test]
+    fn test_course_shapes() {
+        let square = [(0.0, 0.0), (1000.0, 0.0), (1000.0, 1000.0), (0.0, 1000.0), (0.0, 0.0)];
+        let loop_course = detect_topology(&track(&square));
+        assert_eq!(loop_course.topology, RouteTopology::Loop);
+        assert_eq!(loop_course.laps, 1);
+
+        let three_laps: Vec<(f64, f64)> = square.iter().copied()
+            .chain(square[1..].iter().copied())
+            .chain(square[1..].iter().copied())
+            .collect();
+        let laps = detect_topology(&track(&three_laps));
+        assert_eq!(laps.topology, RouteTopology::Loop);
+        assert_eq!(laps.laps, 3);
+        assert!((laps.lap_distance_km.unwrap() - 4.0).abs() < 0.1);
+
+        let out_and_back = detect_topology(&track(&[(0.0, 0.0), (3000.0, 0.0), (3000.0, 2000.0), (3000.0, 0.0), (0.0, 0.0)]));
+        assert_eq!(out_and_back.topology, RouteTopology::OutAndBack);
+        assert!(out_and_back.retraced_fraction > 0.9);
+
+        let lollipop = detect_topology(&track(&[
+            (0.0, 0.0), (1000.0, 0.0), (2000.0, 0.0), (2000.0, 1000.0), (1000.0, 1000.0), (1000.0, 0.0), (0.0, 0.0),
+        ]));
+        assert_eq!(lollipop.topology, RouteTopology::Lollipop);
+
+        let point_to_point = detect_topology(&track(&[(0.0, 0.0), (5000.0, 0.0), (5000.0, 3000.0)]));
+        assert_eq!(point_to_point.topology, RouteTopology::PointToPoint);
+    }
+}
@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+
+/// Elevation smoothing over distance along the track, so the effective
+/// window no longer depends on how densely a device recorded points
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "snake_case")]
+pub enum SmoothingMethod {
+    /// Mean of the samples within `window_m`, trailing or centred on each point
+    MovingAverage { window_m: f64, centered: bool },
+    /// Local least-squares polynomial fit over a centred `window_m`
+    SavitzkyGolay { window_m: f64, polynomial_order: usize },
+    /// Forward-backward (RTS) Kalman smoother with an elevation/gradient state
+    Kalman {
+        /// GPS altitude noise, standard deviation in metres
+        measurement_noise_m: f64,
+        /// Allowed change in gradient per metre travelled
+        process_noise: f64,
+    },
+}
+
+impl Default for SmoothingMethod {
+    fn default() -> Self {
+        SmoothingMethod::MovingAverage {
+            window_m: 100.0,
+            centered: false,
+        }
+    }
+}
+
+impl SmoothingMethod {
+    /// Distance the method averages over, for the methods that have one
+    pub fn window_m(&self) -> Option<f64> {
+        match *self {
+            SmoothingMethod::MovingAverage { window_m, .. } | SmoothingMethod::SavitzkyGolay { window_m, .. } => Some(window_m),
+            SmoothingMethod::Kalman { .. } => None,
+        }
+    }
+}
+
+/// Smooth `values` sampled at cumulative `distances` (both in metres)
+pub fn smooth(distances: &[f64], values: &[f64], method: &SmoothingMethod) -> Vec<f64> {
+    if values.len() < 3 || distances.len() != values.len() {
+        return values.to_vec();
+    }
+
+    match *method {
+        SmoothingMethod::MovingAverage { window_m, centered } => {
+            moving_average(distances, values, window_m, centered)
+        }
+        SmoothingMethod::SavitzkyGolay { window_m, polynomial_order } => {
+            savitzky_golay(distances, values, window_m, polynomial_order)
+        }
+        SmoothingMethod::Kalman { measurement_noise_m, process_noise } => {
+            kalman(distances, values, measurement_noise_m, process_noise)
+        }
+    }
+}
+
+/// Mean over a window in metres rather than a number of points
+pub fn moving_average(distances: &[f64], values: &[f64], window_m: f64, centered: bool) -> Vec<f64> {
+    let (behind, ahead) = if centered {
+        (window_m / 2.0, window_m / 2.0)
+    } else {
+        (window_m, 0.0)
+    };
+
+    // Prefix sums make every window O(1) once its bounds are known
+    let mut prefix = Vec::with_capacity(values.len() + 1);
+    prefix.push(0.0);
+    for value in values {
+        prefix.push(prefix.last().unwrap() + value);
+    }
+
+    let mut start = 0;
+    let mut end = 0;
+    let mut result = Vec::with_capacity(values.len());
+
+    for i in 0..values.len() {
+        while distances[i] - distances[start] > behind {
+            start += 1;
+        }
+        while end + 1 < values.len() && distances[end + 1] - distances[i] <= ahead {
+            end += 1;
+        }
+        let end = end.max(i);
+
+        result.push((prefix[end + 1] - prefix[start]) / (end + 1 - start) as f64);
+    }
+
+    result
+}
+
+/// Savitzky–Golay for unevenly spaced samples: fit a polynomial to the
+/// points within half a window either side and evaluate it at the centre
+pub fn savitzky_golay(distances: &[f64], values: &[f64], window_m: f64, polynomial_order: usize) -> Vec<f64> {
+    let half_window = window_m / 2.0;
+    let order = polynomial_order.clamp(1, 4);
+
+    let mut start = 0;
+    let mut end = 0;
+    let mut result = Vec::with_capacity(values.len());
+
+    for i in 0..values.len() {
+        while distances[i] - distances[start] > half_window {
+            start += 1;
+        }
+        while end + 1 < values.len() && distances[end + 1] - distances[i] <= half_window {
+            end += 1;
+        }
+
+        // Not enough points for the requested order: fall back to a lower one
+        let count = end.max(i) + 1 - start;
+        let order = order.min(count.saturating_sub(1));
+        if order == 0 {
+            result.push(values[start..=end.max(i)].iter().sum::<f64>() / count as f64);
+            continue;
+        }
+
+        let fitted = fit_polynomial(&distances[start..start + count], &values[start..start + count], distances[i], order);
+        result.push(fitted.unwrap_or(values[i]));
+    }
+
+    result
+}
+
+/// Least-squares polynomial fit centred on `x0`, returning its value at `x0`
+fn fit_polynomial(xs: &[f64], ys: &[f64], x0: f64, order: usize) -> Option<f64> {
+    let size = order + 1;
+
+    // Normal equations, with x scaled to keep the matrix well conditioned
+    let scale = xs.iter().map(|x| (x - x0).abs()).fold(1.0, f64::max);
+    let mut matrix = vec![vec![0.0; size + 1]; size];
+
+    for (x, y) in xs.iter().zip(ys) {
+        let t = (x - x0) / scale;
+        let mut powers = vec![1.0; 2 * size];
+        for k in 1..powers.len() {
+            powers[k] = powers[k - 1] * t;
+        }
+        for row in 0..size {
+            for col in 0..size {
+                matrix[row][col] += powers[row + col];
+            }
+            matrix[row][size] += powers[row] * y;
+        }
+    }
+
+    // Gaussian elimination with partial pivoting
+    for col in 0..size {
+        let pivot = (col..size).max_by(|a, b| matrix[*a][col].abs().total_cmp(&matrix[*b][col].abs()))?;
+        if matrix[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+
+        let pivot_row = matrix[col].clone();
+        for (row, values) in matrix.iter_mut().enumerate() {
+            if row != col {
+                let factor = values[col] / pivot_row[col];
+                for (value, pivot_value) in values[col..].iter_mut().zip(&pivot_row[col..]) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+
+    // The constant term is the fitted value at x0
+    Some(matrix[0][size] / matrix[0][0])
+}
+
+/// Kalman filter over distance with state (elevation, gradient), followed by
+/// a Rauch–Tung–Striebel backward pass so the result is not lagged
+pub fn kalman(distances: &[f64], values: &[f64], measurement_noise_m: f64, process_noise: f64) -> Vec<f64> {
+    let n = values.len();
+    let r = measurement_noise_m.max(0.1).powi(2);
+    let q = process_noise.max(1e-9);
+
+    // Filtered and predicted states and covariances, kept for the backward pass
+    let mut filtered_x = vec![[0.0; 2]; n];
+    let mut filtered_p = vec![[[0.0; 2]; 2]; n];
+    let mut predicted_x = vec![[0.0; 2]; n];
+    let mut predicted_p = vec![[[0.0; 2]; 2]; n];
+
+    let mut x = [values[0], 0.0];
+    let mut p = [[r, 0.0], [0.0, 1.0]];
+
+    for i in 0..n {
+        if i > 0 {
+            let d = distances[i] - distances[i - 1];
+
+            // Predict: elevation follows the gradient, gradient drifts with distance
+            x = [x[0] + d * x[1], x[1]];
+            p = [
+                [
+                    p[0][0] + d * (p[0][1] + p[1][0]) + d * d * p[1][1] + q * d.powi(3) / 3.0,
+                    p[0][1] + d * p[1][1] + q * d * d / 2.0,
+                ],
+                [
+                    p[1][0] + d * p[1][1] + q * d * d / 2.0,
+                    p[1][1] + q * d,
+                ],
+            ];
+        }
+        predicted_x[i] = x;
+        predicted_p[i] = p;
+
+        // Update with the measured elevation
+        let s = p[0][0] + r;
+        let k = [p[0][0] / s, p[1][0] / s];
+        let innovation = values[i] - x[0];
+        x = [x[0] + k[0] * innovation, x[1] + k[1] * innovation];
+        p = [
+            [(1.0 - k[0]) * p[0][0], (1.0 - k[0]) * p[0][1]],
+            [p[1][0] - k[1] * p[0][0], p[1][1] - k[1] * p[0][1]],
+        ];
+
+        filtered_x[i] = x;
+        filtered_p[i] = p;
+    }
+
+    // Backward pass
+    let mut smoothed = filtered_x.clone();
+    let mut smoothed_p = filtered_p[n - 1];
+
+    for i in (0..n - 1).rev() {
+        let d = distances[i + 1] - distances[i];
+        let pf = filtered_p[i];
+        let pp = predicted_p[i + 1];
+
+        // C = P_f * F^T * P_p^-1, with F = [[1, d], [0, 1]]
+        let pf_ft = [
+            [pf[0][0] + d * pf[0][1], pf[0][1]],
+            [pf[1][0] + d * pf[1][1], pf[1][1]],
+        ];
+        let det = pp[0][0] * pp[1][1] - pp[0][1] * pp[1][0];
+        if det.abs() < 1e-12 {
+            continue;
+        }
+        let pp_inv = [
+            [pp[1][1] / det, -pp[0][1] / det],
+            [-pp[1][0] / det, pp[0][0] / det],
+        ];
+        let c = [
+            [
+                pf_ft[0][0] * pp_inv[0][0] + pf_ft[0][1] * pp_inv[1][0],
+                pf_ft[0][0] * pp_inv[0][1] + pf_ft[0][1] * pp_inv[1][1],
+            ],
+            [
+                pf_ft[1][0] * pp_inv[0][0] + pf_ft[1][1] * pp_inv[1][0],
+                pf_ft[1][0] * pp_inv[0][1] + pf_ft[1][1] * pp_inv[1][1],
+            ],
+        ];
+
+        let dx = [
+            smoothed[i + 1][0] - predicted_x[i + 1][0],
+            smoothed[i + 1][1] - predicted_x[i + 1][1],
+        ];
+        smoothed[i] = [
+            filtered_x[i][0] + c[0][0] * dx[0] + c[0][1] * dx[1],
+            filtered_x[i][1] + c[1][0] * dx[0] + c[1][1] * dx[1],
+        ];
+
+        let dp = [
+            [smoothed_p[0][0] - pp[0][0], smoothed_p[0][1] - pp[0][1]],
+            [smoothed_p[1][0] - pp[1][0], smoothed_p[1][1] - pp[1][1]],
+        ];
+        let mut next_p = pf;
+        for row in 0..2 {
+            for col in 0..2 {
+                for a in 0..2 {
+                    for b in 0..2 {
+                        next_p[row][col] += c[row][a] * dp[a][b] * c[col][b];
+                    }
+                }
+            }
+        }
+        smoothed_p = next_p;
+    }
+
+    smoothed.into_iter().map(|state| state[0]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_is_distance_based() {
+        // Same profile recorded every 5 m and every 50 m
+        let dense: Vec<f64> = (0..=200).map(|i| i as f64 * 5.0).collect();
+        let sparse: Vec<f64> = (0..=20).map(|i| i as f64 * 50.0).collect();
+        let profile = |d: &f64| if *d >= 500.0 { 100.0 } else { 0.0 };
+
+        let dense_values: Vec<f64> = dense.iter().map(profile).collect();
+        let sparse_values: Vec<f64> = sparse.iter().map(profile).collect();
+
+        let dense_smoothed = moving_average(&dense, &dense_values, 200.0, true);
+        let sparse_smoothed = moving_average(&sparse, &sparse_values, 200.0, true);
+
+        // At 500 m both see the step halfway through a 200 m window; a
+        // point-count window would span 200 m on one and 2 km on the other
+        assert!((dense_smoothed[100] - sparse_smoothed[10]).abs() < 10.0);
+        assert_eq!(dense_smoothed[40], 0.0);
+        assert_eq!(sparse_smoothed[4], 0.0);
+    }
+
+    #[test]
+    fn test_savitzky_golay_preserves_linear_slope() {
+        let distances: Vec<f64> = (0..50).map(|i| i as f64 * 7.0).collect();
+        let values: Vec<f64> = distances.iter().map(|d| 1000.0 + d * 0.1).collect();
+
+        let smoothed = savitzky_golay(&distances, &values, 60.0, 2);
+        for (s, v) in smoothed.iter().zip(&values) {
+            assert!((s - v).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_kalman_reduces_noise() {
+        let distances: Vec<f64> = (0..200).map(|i| i as f64 * 10.0).collect();
+        let truth: Vec<f64> = distances.iter().map(|d| 500.0 + d * 0.05).collect();
+        let noisy: Vec<f64> = truth.iter().enumerate()
+            .map(|(i, t)| t + if i % 2 == 0 { 4.0 } else { -4.0 })
+            .collect();
+
+        let smoothed = kalman(&distances, &noisy, 4.0, 1e-6);
+        let error: f64 = smoothed.iter().zip(&truth).map(|(s, t)| (s - t).abs()).sum::<f64>() / truth.len() as f64;
+        assert!(error < 1.5);
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::algorithms::gradient_analysis::max_gradient;
+use crate::core::algorithms::resampling::slice_profile;
+use crate::core::services::elevation_processor::ElevationData;
+
+/// Distance over which a split's maximum gradient is measured, in metres
+const MAX_GRADIENT_WINDOW_M: f64 = 100.0;
+
+const METRES_PER_MILE: f64 = 1609.344;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SplitUnit {
+    #[serde(rename = "km")]
+    Kilometre,
+    #[serde(rename = "mi")]
+    Mile,
+}
+
+impl SplitUnit {
+    pub fn metres(&self) -> f64 {
+        match self {
+            SplitUnit::Kilometre => 1000.0,
+            SplitUnit::Mile => METRES_PER_MILE,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SplitUnit::Kilometre => "km",
+            SplitUnit::Mile => "mi",
+        }
+    }
+}
+
+/// One row of the split table; distances are in the table's unit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Split {
+    pub index: usize,
+    pub start: f64,
+    pub end: f64,
+    pub distance: f64,
+    pub elevation_gain_m: f64,
+    pub elevation_loss_m: f64,
+    pub average_gradient: f64,
+    pub max_gradient: f64,
+}
+
+/// Break the processed profile into whole km or miles, with a shorter last split
+pub fn split_table(elevation_data: &ElevationData, unit: SplitUnit) -> Vec<Split> {
+    let distances = &elevation_data.cumulative_distance;
+    let altitudes = elevation_data.processed_altitude();
+    let total_m = distances.last().copied().unwrap_or(0.0);
+    let split_m = unit.metres();
+
+    let mut splits = Vec::new();
+    let mut start_m = 0.0;
+
+    while total_m - start_m > 1e-6 {
+        let end_m = (start_m + split_m).min(total_m);
+        let profile = slice_profile(distances, &altitudes, start_m, end_m);
+
+        let mut gain = 0.0;
+        let mut loss = 0.0;
+        for pair in profile.elevation.windows(2) {
+            let change = pair[1] - pair[0];
+            if change > 0.0 {
+                gain += change;
+            } else {
+                loss -= change;
+            }
+        }
+
+        let length_m = end_m - start_m;
+        let net = profile.elevation[profile.elevation.len() - 1] - profile.elevation[0];
+
+        splits.push(Split {
+            index: splits.len() + 1,
+            start: start_m / split_m,
+            end: end_m / split_m,
+            distance: length_m / split_m,
+            elevation_gain_m: gain,
+            elevation_loss_m: loss,
+            average_gradient: net / length_m * 100.0,
+            max_gradient: max_gradient(&profile.distance_m, &profile.elevation, MAX_GRADIENT_WINDOW_M),
+        });
+
+        start_m = end_m;
+    }
+
+    splits
+}
+
+/// Printable CSV of a split table
+pub fn splits_to_csv(splits: &[Split], unit: SplitUnit) -> String {
+    let unit = unit.as_str();
+    let mut csv = format!(
+        "split,start_{unit},end_{unit},distance_{unit},gain_m,loss_m,avg_gradient_pct,max_gradient_pct\n"
+    );
+
+    for split in splits {
+        csv.push_str(&format!(
+            "{},{:.2},{:.2},{:.2},{:.0},{:.0},{:.1},{:.1}\n",
+            split.index,
+            split.start,
+            split.end,
+            split.distance,
+            split.elevation_gain_m,
+            split.elevation_loss_m,
+            split.average_gradient,
+            split.max_gradient,
+        ));
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    #[test]
+    fn test_kilometre_splits() {
+        // ~2.5 km northward: up 50 m over the first km, then down 20 m
+        let (_, elevation_data) = straight_course(226, |d| {
+            if d < 1000.0 { d * 0.05 } else { 50.0 - (d - 1000.0) * 0.0133 }
+        });
+
+        let splits = split_table(&elevation_data, SplitUnit::Kilometre);
+
+        assert_eq!(splits.len(), 3);
+        assert!((splits[0].elevation_gain_m - 50.0).abs() < 1.0);
+        assert!((splits[0].average_gradient - 5.0).abs() < 0.1);
+        assert!(splits[1].elevation_loss_m > 12.0 && splits[1].elevation_gain_m < 0.5);
+        assert!(splits[2].distance < 1.0);
+
+        let csv = splits_to_csv(&splits, SplitUnit::Kilometre);
+        assert!(csv.starts_with("split,start_km,end_km"));
+        assert_eq!(csv.lines().count(), 4);
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::algorithms::resampling::interpolate;
+use crate::core::models::race::GpxData;
+use crate::core::services::elevation_processor::ElevationData;
+
+/// Window lengths analysed when the caller doesn't choose, in metres
+pub const DEFAULT_WINDOWS_M: [f64; 4] = [100.0, 500.0, 1000.0, 5000.0];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseSection {
+    pub start_km: f64,
+    pub end_km: f64,
+    pub start_lat: f64,
+    pub start_lon: f64,
+    pub end_lat: f64,
+    pub end_lon: f64,
+    pub elevation_change_m: f64,
+    pub average_gradient: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteepestSections {
+    pub window_m: f64,
+    /// `None` when the course is shorter than the window
+    pub steepest_ascent: Option<CourseSection>,
+    pub steepest_descent: Option<CourseSection>,
+}
+
+/// Steepest climbing and descending stretch of each window length.
+///
+/// The profile is piecewise linear, so the extremes always start or end on a
+/// recorded point; both alignments are checked for every point.
+pub fn find_steepest_sections(
+    gpx_data: &GpxData,
+    elevation_data: &ElevationData,
+    windows_m: &[f64],
+) -> Vec<SteepestSections> {
+    let distances = &elevation_data.cumulative_distance;
+    let altitudes = elevation_data.processed_altitude();
+    let total_m = distances.last().copied().unwrap_or(0.0);
+
+    windows_m.iter()
+        .map(|&window_m| {
+            if window_m <= 0.0 || window_m > total_m {
+                return SteepestSections {
+                    window_m,
+                    steepest_ascent: None,
+                    steepest_descent: None,
+                };
+            }
+
+            let mut steepest_up: Option<(f64, f64)> = None;
+            let mut steepest_down: Option<(f64, f64)> = None;
+
+            let starts = distances.iter()
+                .copied()
+                .filter(|d| d + window_m <= total_m)
+                .chain(distances.iter().map(|d| d - window_m).filter(|d| *d >= 0.0));
+
+            for start in starts {
+                let change = interpolate(distances, &altitudes, start + window_m)
+                    - interpolate(distances, &altitudes, start);
+
+                if steepest_up.map(|(_, best)| change > best).unwrap_or(true) {
+                    steepest_up = Some((start, change));
+                }
+                if steepest_down.map(|(_, best)| change < best).unwrap_or(true) {
+                    steepest_down = Some((start, change));
+                }
+            }
+
+            let section = |(start, change): (f64, f64)| {
+                course_section(gpx_data, distances, start, start + window_m, change)
+            };
+
+            SteepestSections {
+                window_m,
+                steepest_ascent: steepest_up.filter(|(_, change)| *change > 0.0).map(section),
+                steepest_descent: steepest_down.filter(|(_, change)| *change < 0.0).map(section),
+            }
+        })
+        .collect()
+}
+
+fn course_section(
+    gpx_data: &GpxData,
+    distances: &[f64],
+    start_m: f64,
+    end_m: f64,
+    elevation_change_m: f64,
+) -> CourseSection {
+    let lats: Vec<f64> = gpx_data.points.iter().map(|p| p.lat).collect();
+    let lons: Vec<f64> = gpx_data.points.iter().map(|p| p.lon).collect();
+
+    CourseSection {
+        start_km: start_m / 1000.0,
+        end_km: end_m / 1000.0,
+        start_lat: interpolate(distances, &lats, start_m),
+        start_lon: interpolate(distances, &lons, start_m),
+        end_lat: interpolate(distances, &lats, end_m),
+        end_lon: interpolate(distances, &lons, end_m),
+        elevation_change_m,
+        average_gradient: elevation_change_m / (end_m - start_m) * 100.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    #[test]
+    fn test_steepest_sections() {
+        // ~4.4 km northward: flat, a 300 m wall at 20%, then a long 5% descent
+        let (gpx_data, elevation_data) = straight_course(400, |d| {
+            if d < 1000.0 {
+                500.0
+            } else if d < 1300.0 {
+                500.0 + (d - 1000.0) * 0.2
+            } else {
+                560.0 - (d - 1300.0) * 0.05
+            }
+        });
+
+        let sections = find_steepest_sections(&gpx_data, &elevation_data, &[100.0, 1000.0, 50_000.0]);
+
+        let short = sections[0].steepest_ascent.as_ref().unwrap();
+        assert!((short.average_gradient - 20.0).abs() < 0.5);
+        assert!(short.start_km >= 0.99 && short.end_km <= 1.31);
+
+        let descent = sections[1].steepest_descent.as_ref().unwrap();
+        assert!((descent.average_gradient + 5.0).abs() < 0.5);
+
+        assert!(sections[2].steepest_ascent.is_none());
+    }
+}
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::algorithms::climb_detection::{detect_climbs, ClimbDetectionConfig};
+use crate::core::services::elevation_processor::ElevationData;
+
+/// Length of the sections labelled along the course, in metres
+const SECTION_M: f64 = 1000.0;
+
+/// Gradient in percent from which a stretch counts as steep in the histogram
+const STEEP_GRADIENT: f64 = 15.0;
+
+/// Share of steep distance that moves a course up one class
+const STEEP_SHARE: f64 = 0.25;
+
+/// Altitude from which mountainous terrain is alpine, in metres
+const ALPINE_ALTITUDE_M: f64 = 2500.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerrainClass {
+    Flat,
+    Rolling,
+    Hilly,
+    Mountainous,
+    Alpine,
+}
+
+impl TerrainClass {
+    /// Class from climbing per km, in m/km (mountainous at most)
+    fn from_relief(metres_per_km: f64) -> Self {
+        if metres_per_km >= 40.0 {
+            TerrainClass::Mountainous
+        } else if metres_per_km >= 20.0 {
+            TerrainClass::Hilly
+        } else if metres_per_km >= 10.0 {
+            TerrainClass::Rolling
+        } else {
+            TerrainClass::Flat
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            TerrainClass::Flat => TerrainClass::Rolling,
+            TerrainClass::Rolling => TerrainClass::Hilly,
+            _ => TerrainClass::Mountainous,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TerrainClass::Flat => "flat",
+            TerrainClass::Rolling => "rolling",
+            TerrainClass::Hilly => "hilly",
+            TerrainClass::Mountainous => "mountainous",
+            TerrainClass::Alpine => "alpine",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerrainSection {
+    pub start_km: f64,
+    pub end_km: f64,
+    pub terrain: TerrainClass,
+    /// Half the ascent plus descent per km, so climbs and descents weigh alike
+    pub relief_m_per_km: f64,
+    pub max_altitude_m: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerrainClassification {
+    pub terrain: TerrainClass,
+    /// Ascent per km over the whole course
+    pub overall_uphill_gradient: f64,
+    pub max_altitude_m: f64,
+    pub longest_climb_gain_m: f64,
+    /// Share of the distance steeper than 15% either way
+    pub steep_share: f64,
+    /// Consecutive stretches of the same class, in ~1 km steps
+    pub sections: Vec<TerrainSection>,
+}
+
+/// Classify a course from its climbing rate, highest point, climbs and
+/// gradient histogram, and label the terrain along it
+pub fn classify_terrain(elevation_data: &ElevationData) -> TerrainClassification {
+    let distances = &elevation_data.cumulative_distance;
+    let altitudes = elevation_data.processed_altitude();
+    let total_m = distances.last().copied().unwrap_or(0.0);
+    let max_altitude = altitudes.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let max_altitude = if max_altitude.is_finite() { max_altitude } else { 0.0 };
+
+    let longest_climb = detect_climbs(elevation_data, &ClimbDetectionConfig::default())
+        .iter()
+        .map(|climb| climb.elevation_gain_m)
+        .fold(0.0, f64::max);
+
+    let steep_m: f64 = (1..distances.len())
+        .filter(|&i| elevation_data.gradient_percent[i].abs() >= STEEP_GRADIENT)
+        .map(|i| elevation_data.distance_change[i])
+        .sum();
+    let steep_share = if total_m > 0.0 { steep_m / total_m } else { 0.0 };
+
+    let mut terrain = TerrainClass::from_relief(elevation_data.overall_uphill_gradient);
+    // One long climb makes a course hilly or mountainous even if the rest is flat
+    if longest_climb >= 1200.0 {
+        terrain = terrain.max(TerrainClass::Mountainous);
+    } else if longest_climb >= 600.0 {
+        terrain = terrain.max(TerrainClass::Hilly);
+    }
+    if steep_share >= STEEP_SHARE {
+        terrain = terrain.next();
+    }
+    if terrain == TerrainClass::Mountainous && max_altitude >= ALPINE_ALTITUDE_M {
+        terrain = TerrainClass::Alpine;
+    }
+
+    TerrainClassification {
+        terrain,
+        overall_uphill_gradient: elevation_data.overall_uphill_gradient,
+        max_altitude_m: max_altitude,
+        longest_climb_gain_m: longest_climb,
+        steep_share,
+        sections: terrain_sections(distances, &altitudes),
+    }
+}
+
+fn terrain_sections(distances: &[f64], altitudes: &[f64]) -> Vec<TerrainSection> {
+    let mut sections: Vec<TerrainSection> = Vec::new();
+    if distances.len() < 2 {
+        return sections;
+    }
+
+    // Relief and highest point of each fixed-length step
+    let mut steps: Vec<(f64, f64, f64)> = Vec::new();
+    for i in 1..distances.len() {
+        let midpoint = (distances[i - 1] + distances[i]) / 2.0;
+        let step = (midpoint / SECTION_M).floor() as usize;
+        if steps.len() <= step {
+            steps.resize(step + 1, (0.0, 0.0, f64::NEG_INFINITY));
+        }
+        let entry = &mut steps[step];
+        entry.0 += distances[i] - distances[i - 1];
+        entry.1 += (altitudes[i] - altitudes[i - 1]).abs() / 2.0;
+        entry.2 = entry.2.max(altitudes[i - 1]).max(altitudes[i]);
+    }
+
+    let mut start_m = 0.0;
+    for (distance, relief, max_altitude) in steps {
+        if distance <= 0.0 {
+            continue;
+        }
+        let relief_m_per_km = relief / (distance / 1000.0);
+        let mut terrain = TerrainClass::from_relief(relief_m_per_km);
+        if terrain == TerrainClass::Mountainous && max_altitude >= ALPINE_ALTITUDE_M {
+            terrain = TerrainClass::Alpine;
+        }
+        let end_m = start_m + distance;
+
+        match sections.last_mut() {
+            Some(last) if last.terrain == terrain => {
+                let last_km = last.end_km - last.start_km;
+                last.relief_m_per_km = (last.relief_m_per_km * last_km + relief) / (last_km + distance / 1000.0);
+                last.end_km = end_m / 1000.0;
+                last.max_altitude_m = last.max_altitude_m.max(max_altitude);
+            }
+            _ => sections.push(TerrainSection {
+                start_km: start_m / 1000.0,
+                end_km: end_m / 1000.0,
+                terrain,
+                relief_m_per_km,
+                max_altitude_m: max_altitude,
+            }),
+        }
+        start_m = end_m;
+    }
+
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    /// ~11 km northwards
+    fn course(elevation: impl Fn(f64) -> f64) -> ElevationData {
+        straight_course(1000, elevation).1
+    }
+
+    #[test]
+    fn test_terrain_classes() {
+        let flat = classify_terrain(&course(|_| 200.0));
+        assert_eq!(flat.terrain, TerrainClass::Flat);
+        assert_eq!(flat.sections.len(), 1);
+
+        // 5 km up at 15% then flat: one long climb with steep ground
+        let climb = classify_terrain(&course(|d| 1000.0 + d.min(5000.0) * 0.15));
+        assert_eq!(climb.terrain, TerrainClass::Mountainous);
+        assert_eq!(climb.sections.len(), 2);
+        assert_eq!(climb.sections[0].terrain, TerrainClass::Mountainous);
+        assert_eq!(climb.sections[1].terrain, TerrainClass::Flat);
+        assert!((climb.sections[0].end_km - 5.0).abs() < 0.1);
+
+        // The same climb above 2500 m is alpine
+        let high = classify_terrain(&course(|d| 2000.0 + d.min(5000.0) * 0.15));
+        assert_eq!(high.terrain, TerrainClass::Alpine);
+    }
+}
@@ -0,0 +1,276 @@
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::core::algorithms::linear_referencing::distance_m;
+use crate::core::models::race::GpxPoint;
+use crate::core::services::gpx_parser::MAX_POINTS;
+
+/// A jump is never shorter than this, however sparse the track, in metres
+const MIN_JUMP_M: f64 = 200.0;
+
+/// A jump is this many times the typical spacing between points
+const JUMP_FACTOR: f64 = 20.0;
+
+/// Longest run of stray points that still counts as one glitch
+const MAX_GLITCH_POINTS: usize = 5;
+
+/// Longer gaps are reported but not filled: nothing is known of the way
+/// between, in metres
+const MAX_FILLED_GAP_M: f64 = 10_000.0;
+
+/// Faster than this between timestamps is not a person moving, in m/s
+const MAX_PLAUSIBLE_SPEED_MPS: f64 = 40.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectionKind {
+    /// Points that jumped off the course and back, removed
+    Teleport,
+    /// A straight-line gap in the recording, e.g. a tunnel
+    Gap,
+    /// Elevations outside the plausible range, replaced by the average
+    InvalidElevation,
+    /// A single-point elevation spike, flattened
+    ElevationSpike,
+}
+
+/// One fix made to a track on import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackCorrection {
+    pub kind: CorrectionKind,
+    /// Where the correction starts
+    pub lat: f64,
+    pub lon: f64,
+    /// Points removed, added or changed
+    pub points: usize,
+    /// Size of the problem: furthest stray point or gap length in metres,
+    /// elevation change for elevation fixes
+    pub magnitude: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrackRepairOptions {
+    /// Fill gaps with points at the track's usual spacing
+    pub interpolate_gaps: bool,
+}
+
+/// Remove points that jump off the course and straight back, and report (and
+/// optionally fill) gaps where the track jumps and carries on from there.
+///
+/// A jump is a step far longer than the track's usual spacing or, when both
+/// points have timestamps, one covered faster than anyone moves.
+pub fn repair_track(points: &mut Vec<GpxPoint>, options: &TrackRepairOptions) -> Vec<TrackCorrection> {
+    let mut corrections = Vec::new();
+    if points.len() < 3 {
+        return corrections;
+    }
+
+    let mut steps: Vec<f64> = points.windows(2)
+        .map(|pair| distance_m(&pair[0], &pair[1]))
+        .collect();
+    steps.sort_by(|a, b| a.total_cmp(b));
+    let spacing = steps[steps.len() / 2];
+    let jump_m = (spacing * JUMP_FACTOR).max(MIN_JUMP_M);
+
+    let is_jump = |from: &GpxPoint, to: &GpxPoint| {
+        let distance = distance_m(from, to);
+        distance > jump_m || speed_mps(from, to, distance).is_some_and(|speed| speed > MAX_PLAUSIBLE_SPEED_MPS)
+    };
+
+    // A glitch at the very start has no course before it to return to, so it
+    // shows as a jump onto a track that carries on normally
+    let start = (1..=MAX_GLITCH_POINTS.min(points.len() - 2))
+        .find(|&k| is_jump(&points[k - 1], &points[k]))
+        .filter(|&k| !is_jump(&points[k], &points[k + 1]))
+        .unwrap_or(0);
+    if start > 0 {
+        let furthest = points[..start].iter()
+            .map(|point| distance_m(&points[start], point))
+            .fold(0.0, f64::max);
+        corrections.push(TrackCorrection {
+            kind: CorrectionKind::Teleport,
+            lat: points[start].lat,
+            lon: points[start].lon,
+            points: start,
+            magnitude: furthest,
+        });
+    }
+
+    let mut repaired: Vec<GpxPoint> = Vec::with_capacity(points.len());
+    repaired.push(points[start].clone());
+    let mut i = start + 1;
+    // Points that may be added to fill gaps, so the track stays within a
+    // downsample of the upload limit
+    let mut fill_budget = (MAX_POINTS * 2).saturating_sub(points.len());
+
+    while i < points.len() {
+        let last = repaired.last().unwrap().clone();
+        if !is_jump(&last, &points[i]) {
+            repaired.push(points[i].clone());
+            i += 1;
+            continue;
+        }
+
+        // Off the course and back: the track returns near where it left
+        let back = (i + 1..points.len().min(i + 1 + MAX_GLITCH_POINTS))
+            .find(|&j| !is_jump(&last, &points[j]));
+        if let Some(back) = back {
+            let furthest = points[i..back].iter()
+                .map(|point| distance_m(&last, point))
+                .fold(0.0, f64::max);
+            corrections.push(TrackCorrection {
+                kind: CorrectionKind::Teleport,
+                lat: last.lat,
+                lon: last.lon,
+                points: back - i,
+                magnitude: furthest,
+            });
+            i = back;
+            continue;
+        }
+
+        let gap = distance_m(&last, &points[i]);
+        let mut added = 0;
+        if options.interpolate_gaps && spacing > 0.0 && gap <= MAX_FILLED_GAP_M {
+            // Spread out further once the budget runs short
+            let count = ((gap / spacing).ceil() as usize).min(fill_budget + 1);
+            for step in 1..count {
+                let t = step as f64 / count as f64;
+                repaired.push(GpxPoint {
+                    lat: last.lat + t * (points[i].lat - last.lat),
+                    lon: last.lon + t * (points[i].lon - last.lon),
+                    ele: last.ele + t * (points[i].ele - last.ele),
+                    time: None,
+                });
+                added += 1;
+            }
+            fill_budget -= added;
+        }
+        corrections.push(TrackCorrection {
+            kind: CorrectionKind::Gap,
+            lat: last.lat,
+            lon: last.lon,
+            points: added,
+            magnitude: gap,
+        });
+        repaired.push(points[i].clone());
+        i += 1;
+    }
+
+    *points = repaired;
+    corrections
+}
+
+fn speed_mps(from: &GpxPoint, to: &GpxPoint, distance: f64) -> Option<f64> {
+    let from = DateTime::parse_from_rfc3339(from.time.as_deref()?).ok()?;
+    let to = DateTime::parse_from_rfc3339(to.time.as_deref()?).ok()?;
+    let seconds = (to - from).num_milliseconds() as f64 / 1000.0;
+    (seconds > 0.0).then(|| distance / seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    fn straight(count: usize) -> Vec<GpxPoint> {
+        straight_course(count, |_| 500.0).0.points
+    }
+
+    #[test]
+    fn test_teleports_removed() {
+        let mut points = straight(50);
+        // Two points ~2 km east, then straight back onto the course
+        points[20].lon = 7.026;
+        points[21].lon = 7.026;
+
+        let corrections = repair_track(&mut points, &TrackRepairOptions::default());
+
+        assert_eq!(points.len(), 48);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].kind, CorrectionKind::Teleport);
+        assert_eq!(corrections[0].points, 2);
+        assert!(corrections[0].magnitude > 1900.0);
+    }
+
+    #[test]
+    fn test_glitched_first_point_removed() {
+        let mut points = straight(50);
+        points[0].lon = 7.026;
+
+        let corrections = repair_track(&mut points, &TrackRepairOptions::default());
+
+        assert_eq!(points.len(), 49);
+        assert_eq!(points[0].lon, 7.0);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].kind, CorrectionKind::Teleport);
+        assert_eq!(corrections[0].points, 1);
+    }
+
+    #[test]
+    fn test_gap_filled_when_asked() {
+        // ~1 km with no points in the middle, as through a tunnel
+        let mut points = straight(20);
+        points.extend(straight(20).into_iter().map(|p| GpxPoint { lat: p.lat + 0.011, ..p }));
+        let mut filled = points.clone();
+
+        let reported = repair_track(&mut points, &TrackRepairOptions::default());
+        assert_eq!(points.len(), 40);
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].kind, CorrectionKind::Gap);
+        assert_eq!(reported[0].points, 0);
+
+        let interpolated = repair_track(&mut filled, &TrackRepairOptions { interpolate_gaps: true });
+        assert_eq!(interpolated[0].points, filled.len() - 40);
+        assert!(filled.windows(2).all(|pair| distance_m(&pair[0], &pair[1]) < 20.0));
+    }
+
+    #[test]
+    fn test_long_gaps_not_filled() {
+        // ~1000 km between two short stretches
+        let mut points = straight(20);
+        points.extend(straight(20).into_iter().map(|p| GpxPoint { lat: p.lat + 9.0, ..p }));
+
+        let corrections = repair_track(&mut points, &TrackRepairOptions { interpolate_gaps: true });
+
+        assert_eq!(points.len(), 40);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].kind, CorrectionKind::Gap);
+        assert_eq!(corrections[0].points, 0);
+        assert!(corrections[0].magnitude > 990_000.0);
+    }
+
+    #[test]
+    fn test_gap_filling_bounded() {
+        // Many ~9 km gaps, each worth ~800 points at the track's spacing
+        let mut points = Vec::new();
+        for stretch in 0..300 {
+            points.extend(straight(10).into_iter().map(|p| GpxPoint { lat: p.lat + stretch as f64 * 0.08, ..p }));
+        }
+        let original = points.len();
+
+        let corrections = repair_track(&mut points, &TrackRepairOptions { interpolate_gaps: true });
+
+        assert_eq!(corrections.len(), 299);
+        assert!(corrections.iter().all(|c| c.kind == CorrectionKind::Gap));
+        assert!(points.len() <= MAX_POINTS * 2, "{}", points.len());
+        assert_eq!(corrections.iter().map(|c| c.points).sum::<usize>(), points.len() - original);
+    }
+
+    #[test]
+    fn test_speed_jump_with_timestamps() {
+        // 100 m in one second is short enough to pass the distance check
+        let mut points: Vec<GpxPoint> = straight(30).into_iter()
+            .enumerate()
+            .map(|(i, p)| GpxPoint { time: Some(format!("2024-06-01T08:{:02}:{:02}Z", i * 5 / 60, i * 5 % 60)), ..p })
+            .collect();
+        points[10].lon += 0.0013;
+        points[10].time = Some("2024-06-01T08:00:46Z".to_string());
+
+        let corrections = repair_track(&mut points, &TrackRepairOptions::default());
+
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].kind, CorrectionKind::Teleport);
+        assert_eq!(points.len(), 29);
+    }
+}
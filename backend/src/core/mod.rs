@@ -1,3 +1,5 @@
 pub mod models;
 pub mod services;
 pub mod algorithms;
+#[cfg(test)]
+pub mod test_support;
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::models::race::GradientDistribution;
+use crate::core::services::effort_model::EffortModelKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AidService {
+    Water,
+    Food,
+    Medical,
+    Toilets,
+    DropBag,
+    Crew,
+    Sleep,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AidStation {
+    pub id: String,
+    pub race_id: String,
+    pub name: String,
+    /// Position along the track, after snapping
+    pub distance_km: f64,
+    pub lat: f64,
+    pub lon: f64,
+    pub services: Vec<AidService>,
+    pub created_at: Option<String>,
+}
+
+/// An aid station placed either by distance or by coordinates
+#[derive(Debug, Clone, Deserialize)]
+pub struct AidStationInput {
+    pub name: String,
+    pub distance_km: Option<f64>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    #[serde(default)]
+    pub services: Vec<AidService>,
+}
+
+/// The stretch of course between two aid stations (or the start and finish)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Leg {
+    pub index: usize,
+    pub from: String,
+    pub to: String,
+    pub start_km: f64,
+    pub end_km: f64,
+    pub distance_km: f64,
+    pub elevation_gain_m: f64,
+    pub elevation_loss_m: f64,
+    pub effort_model: EffortModelKind,
+    pub effort_distance_km: f64,
+    pub gradient_distribution: GradientDistribution,
+}
@@ -1,3 +1,7 @@
 pub mod race;
 pub mod user;
 pub mod synthesis;
+pub mod processing;
+pub mod pacing;
+pub mod aid_station;
+pub mod sport;
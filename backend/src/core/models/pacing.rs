@@ -0,0 +1,88 @@
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Checkpoint {
+    pub id: String,
+    pub race_id: String,
+    pub name: String,
+    pub distance_km: f64,
+    /// Organiser cut-off as a clock time, `HH:MM`
+    pub cutoff_time: Option<String>,
+    /// Days after the start day the cut-off falls on
+    pub cutoff_day: i64,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckpointInput {
+    pub name: String,
+    pub distance_km: f64,
+    pub cutoff_time: Option<String>,
+    #[serde(default)]
+    pub cutoff_day: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedCheckpoint {
+    pub name: String,
+    pub distance_km: f64,
+    pub elapsed_seconds: f64,
+    /// Planned arrival as a clock time, `HH:MM:SS`
+    pub arrival_time: String,
+    /// Days after the start day the arrival falls on
+    pub arrival_day: i64,
+    pub cutoff_time: Option<String>,
+    pub cutoff_day: Option<i64>,
+    /// Time in hand at the cut-off; negative when the plan misses it
+    pub margin_seconds: Option<f64>,
+    pub misses_cutoff: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PacingPlan {
+    pub start_time: String,
+    pub target_seconds: f64,
+    pub checkpoints: Vec<PlannedCheckpoint>,
+    pub missed_cutoffs: usize,
+}
+
+/// Sun events on one day at the course, in the race's local time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SunTimes {
+    pub date: NaiveDate,
+    pub civil_dawn: Option<DateTime<FixedOffset>>,
+    pub sunrise: Option<DateTime<FixedOffset>>,
+    pub sunset: Option<DateTime<FixedOffset>>,
+    pub civil_dusk: Option<DateTime<FixedOffset>>,
+}
+
+/// Stretch of the course reached between civil dusk and civil dawn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DarkSection {
+    pub start_km: f64,
+    pub end_km: f64,
+    pub distance_km: f64,
+    pub start_time: DateTime<FixedOffset>,
+    pub end_time: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointLight {
+    pub name: String,
+    pub distance_km: f64,
+    pub arrival: DateTime<FixedOffset>,
+    pub in_darkness: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaylightPlan {
+    pub start: DateTime<FixedOffset>,
+    pub finish: DateTime<FixedOffset>,
+    pub target_seconds: f64,
+    pub sun_times: Vec<SunTimes>,
+    pub dark_sections: Vec<DarkSection>,
+    pub dark_distance_km: f64,
+    pub checkpoints: Vec<CheckpointLight>,
+}
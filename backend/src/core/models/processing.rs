@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::algorithms::smoothing::SmoothingMethod;
+
+/// Gradient limits for routes up to a hilliness, in metres climbed per km
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CappingThreshold {
+    /// Applies to routes climbing less than this many metres per km;
+    /// `f64::MAX` rather than infinity for the last band, so it survives JSON
+    pub hilliness_limit: f64,
+    pub max_up: f64,
+    pub max_down: f64,
+}
+
+impl CappingThreshold {
+    pub const fn new(hilliness_limit: f64, max_up: f64, max_down: f64) -> Self {
+        Self { hilliness_limit, max_up, max_down }
+    }
+}
+
+/// Capping table for foot races on mixed terrain
+pub const TRAIL_CAPPING: [CappingThreshold; 6] = [
+    CappingThreshold::new(20.0, 15.0, 12.0),
+    CappingThreshold::new(30.0, 20.0, 15.0),
+    CappingThreshold::new(40.0, 25.0, 20.0),
+    CappingThreshold::new(50.0, 32.0, 27.0),
+    CappingThreshold::new(60.0, 35.0, 31.0),
+    CappingThreshold::new(f64::MAX, 40.0, 36.0),
+];
+
+/// How elevation data is processed before metrics are derived from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessingOptions {
+    pub smoothed: bool,
+    pub smoothing: SmoothingMethod,
+    /// Smoothing is skipped on routes climbing more than this many metres per km
+    pub smoothing_max_hilliness: f64,
+    pub capping: bool,
+    /// Ordered by `hilliness_limit`; the first one above the route's hilliness applies
+    pub capping_thresholds: Vec<CappingThreshold>,
+    /// Distance over which gradients are measured for the distribution, in metres
+    pub gradient_window_m: f64,
+}
+
+impl Default for ProcessingOptions {
+    fn default() -> Self {
+        Self {
+            smoothed: true,
+            smoothing: SmoothingMethod::default(),
+            smoothing_max_hilliness: 20.0,
+            capping: true,
+            capping_thresholds: TRAIL_CAPPING.to_vec(),
+            gradient_window_m: 75.0,
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::core::algorithms::gradient_analysis::GradientBins;
+use crate::core::models::processing::ProcessingOptions;
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Race {
     pub id: String,
@@ -11,9 +14,62 @@ pub struct Race {
     pub elevation_gain_m: f64,
     pub elevation_loss_m: f64,
     pub itra_effort_distance: Option<f64>,
+    /// UTMB/ITRA category band, XXS to XXL
+    pub category: String,
+    /// 0 to 12, see `calculate_mountain_level`
+    pub mountain_level: i64,
+    pub elevation_source: String,
+    /// See `Sport`; picks the processing profile
+    pub sport: String,
+    /// See `TerrainClass`; none until the race has been classified
+    pub terrain: Option<String>,
+    /// See `RouteTopology`; none until the course shape has been detected
+    pub topology: Option<String>,
+    pub laps: i64,
     pub created_at: Option<String>,
 }
 
+/// Where a race's stored elevations come from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevationSource {
+    Gps,
+    Dem,
+    Blended,
+}
+
+impl ElevationSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ElevationSource::Gps => "gps",
+            ElevationSource::Dem => "dem",
+            ElevationSource::Blended => "blended",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [ElevationSource::Gps, ElevationSource::Dem, ElevationSource::Blended]
+            .into_iter()
+            .find(|source| source.as_str() == value)
+    }
+}
+
+/// How DEM elevations are applied to a track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ElevationCorrectionMode {
+    Replace,
+    /// Weighted average, `dem_weight` in 0..=1
+    Blend { dem_weight: f64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElevationCorrectionResult {
+    pub race: Race,
+    pub elevation_source: ElevationSource,
+    pub points_corrected: usize,
+    pub points_total: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpxPoint {
     pub lat: f64,
@@ -33,6 +89,16 @@ pub struct ElevationProfile {
     pub elevation: Vec<f64>,
     pub smoothed: bool,
     pub window_size: u32,
+    pub smoothing_applied: bool,
+    pub options: ProcessingOptions,
+    /// Bucket extremes, present when the profile is resampled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_elevation: Option<Vec<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_elevation: Option<Vec<f64>>,
+    /// Sample spacing in metres, when resampled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spacing_m: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +110,12 @@ pub struct GradientBin {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GradientDistribution {
+    /// Percentages are shares of the total ascending distance
     pub ascent: Vec<GradientBin>,
+    /// Percentages are shares of the total descending distance
     pub descent: Vec<GradientBin>,
+    /// Percentage is a share of the whole course
+    pub flat: GradientBin,
+    pub bins: GradientBins,
+    pub options: ProcessingOptions,
 }
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sport {
+    #[default]
+    TrailRun,
+    RoadRun,
+    RoadBike,
+    Gravel,
+    Mtb,
+    Hike,
+    SkiTouring,
+}
+
+impl Sport {
+    pub const ALL: [Sport; 7] = [
+        Sport::TrailRun,
+        Sport::RoadRun,
+        Sport::RoadBike,
+        Sport::Gravel,
+        Sport::Mtb,
+        Sport::Hike,
+        Sport::SkiTouring,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sport::TrailRun => "trail_run",
+            Sport::RoadRun => "road_run",
+            Sport::RoadBike => "road_bike",
+            Sport::Gravel => "gravel",
+            Sport::Mtb => "mtb",
+            Sport::Hike => "hike",
+            Sport::SkiTouring => "ski_touring",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|sport| sport.as_str() == value.trim())
+    }
+
+    pub fn is_cycling(&self) -> bool {
+        matches!(self, Sport::RoadBike | Sport::Gravel | Sport::Mtb)
+    }
+}
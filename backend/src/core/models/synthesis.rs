@@ -18,6 +18,8 @@ pub struct RouteCandidate {
     pub elevation_gain_m: f64,
     pub elevation_loss_m: f64,
     pub itra_effort_distance: f64,
+    /// Stored terrain class of the race; none until it has been classified
+    pub terrain: Option<String>,
     pub similarity_score: f64,
     pub route: RouteData,
 }
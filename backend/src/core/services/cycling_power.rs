@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::services::elevation_processor::ElevationData;
+
+const GRAVITY: f64 = 9.80665;
+
+/// Fastest speed assumed on descents, in m/s (~72 km/h)
+const MAX_SPEED_MS: f64 = 20.0;
+
+/// Rider, bike and drivetrain parameters for the physics model
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RiderSetup {
+    pub rider_mass_kg: f64,
+    pub bike_mass_kg: f64,
+    /// Drag area in m²
+    pub cda_m2: f64,
+    /// Rolling resistance coefficient
+    pub crr: f64,
+    /// Share of pedal power lost in the drivetrain
+    pub drivetrain_loss: f64,
+}
+
+impl Default for RiderSetup {
+    fn default() -> Self {
+        Self {
+            rider_mass_kg: 75.0,
+            bike_mass_kg: 9.0,
+            cda_m2: 0.32,
+            crr: 0.005,
+            drivetrain_loss: 0.025,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerTarget {
+    /// Hold this speed everywhere and estimate the power it needs
+    SpeedKmh(f64),
+    /// Ride at this power everywhere and estimate the speed it gives
+    PowerW(f64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerSplit {
+    pub km: usize,
+    pub distance_km: f64,
+    pub average_gradient: f64,
+    pub time_seconds: f64,
+    pub average_speed_kmh: f64,
+    pub average_power_w: f64,
+    pub energy_kj: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerEstimate {
+    pub target: PowerTarget,
+    pub setup: RiderSetup,
+    pub splits: Vec<PowerSplit>,
+    pub total_time_seconds: f64,
+    pub average_speed_kmh: f64,
+    pub average_power_w: f64,
+    pub total_energy_kj: f64,
+}
+
+/// Air density in kg/m³ at `altitude_m` in the standard atmosphere
+pub fn air_density(altitude_m: f64) -> f64 {
+    1.225 * (1.0 - 2.25577e-5 * altitude_m.clamp(-500.0, 9000.0)).powf(4.2559)
+}
+
+/// Resistive forces at a gradient, split into the speed-independent part
+/// (gravity and rolling) and the drag coefficient to multiply by v²
+fn forces(setup: &RiderSetup, gradient_percent: f64, altitude_m: f64) -> (f64, f64) {
+    let mass = setup.rider_mass_kg + setup.bike_mass_kg;
+    let angle = (gradient_percent / 100.0).atan();
+    let constant = mass * GRAVITY * (angle.sin() + setup.crr * angle.cos());
+    let drag = 0.5 * air_density(altitude_m) * setup.cda_m2;
+    (constant, drag)
+}
+
+/// Pedal power in watts to hold `speed_ms`; zero where the rider could coast
+pub fn power_for_speed(setup: &RiderSetup, gradient_percent: f64, altitude_m: f64, speed_ms: f64) -> f64 {
+    let (constant, drag) = forces(setup, gradient_percent, altitude_m);
+    let wheel_power = speed_ms * (constant + drag * speed_ms * speed_ms);
+    (wheel_power / (1.0 - setup.drivetrain_loss)).max(0.0)
+}
+
+/// Steady speed in m/s reached with `power_w` at the pedals, capped on descents
+pub fn speed_for_power(setup: &RiderSetup, gradient_percent: f64, altitude_m: f64, power_w: f64) -> f64 {
+    let (constant, drag) = forces(setup, gradient_percent, altitude_m);
+    let wheel_power = power_w.max(0.0) * (1.0 - setup.drivetrain_loss);
+
+    // f(v) = drag·v³ + constant·v − P is convex for v > 0, so Newton's method
+    // from the right converges monotonically onto the largest root
+    let f = |v: f64| drag * v.powi(3) + constant * v - wheel_power;
+    if f(MAX_SPEED_MS) <= 0.0 {
+        return MAX_SPEED_MS;
+    }
+
+    let mut v = MAX_SPEED_MS;
+    for _ in 0..50 {
+        let slope = 3.0 * drag * v * v + constant;
+        if slope <= 0.0 {
+            break;
+        }
+        let next = v - f(v) / slope;
+        if (v - next).abs() < 1e-6 {
+            v = next;
+            break;
+        }
+        v = next;
+    }
+
+    v.max(0.5)
+}
+
+/// Speed and power over every step of the course, totalled per km
+pub fn estimate_power(elevation_data: &ElevationData, setup: &RiderSetup, target: PowerTarget) -> PowerEstimate {
+    let altitudes = elevation_data.processed_altitude();
+    let mut splits: Vec<PowerSplit> = Vec::new();
+    // Per-km accumulators: distance, time, energy, climb
+    let mut buckets: Vec<(f64, f64, f64, f64)> = Vec::new();
+
+    for i in 1..elevation_data.cumulative_distance.len() {
+        let distance_m = elevation_data.distance_change[i];
+        if distance_m <= 0.0 {
+            continue;
+        }
+        let gradient = elevation_data.gradient_percent[i];
+        let altitude = (altitudes[i - 1] + altitudes[i]) / 2.0;
+
+        let (speed, power) = match target {
+            PowerTarget::SpeedKmh(kmh) => {
+                let speed = kmh / 3.6;
+                (speed, power_for_speed(setup, gradient, altitude, speed))
+            }
+            PowerTarget::PowerW(watts) => (speed_for_power(setup, gradient, altitude, watts), watts),
+        };
+        let time = distance_m / speed;
+
+        let midpoint = elevation_data.cumulative_distance[i] - distance_m / 2.0;
+        let km = (midpoint / 1000.0).floor() as usize;
+        if buckets.len() <= km {
+            buckets.resize(km + 1, (0.0, 0.0, 0.0, 0.0));
+        }
+        let bucket = &mut buckets[km];
+        bucket.0 += distance_m;
+        bucket.1 += time;
+        bucket.2 += power * time;
+        bucket.3 += elevation_data.altitude_change[i];
+    }
+
+    for (index, (distance, time, energy, climb)) in buckets.into_iter().enumerate() {
+        if distance <= 0.0 {
+            continue;
+        }
+        splits.push(PowerSplit {
+            km: index + 1,
+            distance_km: distance / 1000.0,
+            average_gradient: climb / distance * 100.0,
+            time_seconds: time,
+            average_speed_kmh: distance / time * 3.6,
+            average_power_w: energy / time,
+            energy_kj: energy / 1000.0,
+        });
+    }
+
+    let total_time: f64 = splits.iter().map(|s| s.time_seconds).sum();
+    let total_distance: f64 = splits.iter().map(|s| s.distance_km).sum();
+    let total_energy: f64 = splits.iter().map(|s| s.energy_kj).sum();
+
+    PowerEstimate {
+        target,
+        setup: *setup,
+        splits,
+        total_time_seconds: total_time,
+        average_speed_kmh: if total_time > 0.0 { total_distance / total_time * 3600.0 } else { 0.0 },
+        average_power_w: if total_time > 0.0 { total_energy * 1000.0 / total_time } else { 0.0 },
+        total_energy_kj: total_energy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_and_speed_are_inverse() {
+        let setup = RiderSetup::default();
+
+        for gradient in [-4.0, 0.0, 3.0, 8.0] {
+            let power = power_for_speed(&setup, gradient, 500.0, 8.0);
+            if power > 0.0 {
+                let speed = speed_for_power(&setup, gradient, 500.0, power);
+                assert!((speed - 8.0).abs() < 1e-3, "gradient {}", gradient);
+            }
+        }
+
+        // Roughly 200 W for 33 km/h on the flat with these defaults
+        let flat = power_for_speed(&setup, 0.0, 0.0, 33.0 / 3.6);
+        assert!(flat > 170.0 && flat < 230.0);
+
+        // Thinner air at altitude means less drag
+        assert!(air_density(2000.0) < air_density(0.0));
+    }
+}
@@ -0,0 +1,168 @@
+use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+
+use crate::core::algorithms::linear_referencing::point_at;
+use crate::core::models::pacing::{Checkpoint, CheckpointLight, DarkSection, DaylightPlan, SunTimes};
+use crate::core::models::race::GpxData;
+use crate::core::services::elevation_processor::ElevationData;
+use crate::core::services::pace_predictor::cumulative_adjusted_distance;
+use crate::core::services::pacing_plan::build_pacing_plan;
+use crate::core::services::solar::{solar_elevation, sun_crossing, CIVIL_TWILIGHT_ZENITH, SUNRISE_ZENITH};
+
+/// Sun elevation below which it is dark enough to need a headlamp
+const DARK_ELEVATION_DEG: f64 = 90.0 - CIVIL_TWILIGHT_ZENITH;
+
+/// Longest target time a plan is built for: sun times are worked out for every day
+pub const MAX_TARGET_SECONDS: f64 = 30.0 * 86_400.0;
+
+/// Time the runner reaches each stretch of the course on an effort-even plan
+/// finishing in `target_seconds`, and which of those stretches are in darkness
+pub fn build_daylight_plan(
+    gpx_data: &GpxData,
+    elevation_data: &ElevationData,
+    checkpoints: &[Checkpoint],
+    start: DateTime<FixedOffset>,
+    target_seconds: f64,
+) -> Result<DaylightPlan, String> {
+    let finish = add_seconds(start, target_seconds)
+        .ok_or_else(|| "Finish time is out of range".to_string())?;
+    let offset = *start.offset();
+    let distances = &elevation_data.cumulative_distance;
+    let adjusted = cumulative_adjusted_distance(elevation_data);
+    let total_adjusted = adjusted.last().copied().unwrap_or(0.0);
+
+    // Same split of the target time as the pacing plan
+    let time_at = |i: usize| {
+        let elapsed = if total_adjusted > 0.0 { target_seconds * adjusted[i] / total_adjusted } else { 0.0 };
+        // Never later than the finish, which is known to be in range
+        add_seconds(start.with_timezone(&Utc), elapsed).unwrap_or(finish.with_timezone(&Utc))
+    };
+    let is_dark = |at: DateTime<Utc>, lat: f64, lon: f64| solar_elevation(at, lat, lon) < DARK_ELEVATION_DEG;
+
+    let mut dark_sections: Vec<DarkSection> = Vec::new();
+    let mut in_section = false;
+    for i in 1..distances.len().min(gpx_data.points.len()) {
+        let (a, b) = (&gpx_data.points[i - 1], &gpx_data.points[i]);
+        let (from, to) = (time_at(i - 1), time_at(i));
+        let midpoint = from + (to - from) / 2;
+
+        if !is_dark(midpoint, (a.lat + b.lat) / 2.0, (a.lon + b.lon) / 2.0) {
+            in_section = false;
+            continue;
+        }
+
+        match dark_sections.last_mut() {
+            Some(section) if in_section => {
+                section.end_km = distances[i] / 1000.0;
+                section.distance_km = section.end_km - section.start_km;
+                section.end_time = to.with_timezone(&offset);
+            }
+            _ => dark_sections.push(DarkSection {
+                start_km: distances[i - 1] / 1000.0,
+                end_km: distances[i] / 1000.0,
+                distance_km: (distances[i] - distances[i - 1]) / 1000.0,
+                start_time: from.with_timezone(&offset),
+                end_time: to.with_timezone(&offset),
+            }),
+        }
+        in_section = true;
+    }
+
+    // Sun times at the start for every day the race runs over
+    let (lat, lon) = gpx_data.points.first().map(|p| (p.lat, p.lon)).unwrap_or((0.0, 0.0));
+    let sun_times: Vec<SunTimes> = start.date_naive()
+        .iter_days()
+        .take_while(|date| *date <= finish.date_naive())
+        .map(|date| {
+            let crossing = |zenith: f64, rising: bool| {
+                sun_crossing(date, lat, lon, zenith, rising).map(|at| at.with_timezone(&offset))
+            };
+            SunTimes {
+                date,
+                civil_dawn: crossing(CIVIL_TWILIGHT_ZENITH, true),
+                sunrise: crossing(SUNRISE_ZENITH, true),
+                sunset: crossing(SUNRISE_ZENITH, false),
+                civil_dusk: crossing(CIVIL_TWILIGHT_ZENITH, false),
+            }
+        })
+        .collect();
+
+    let plan = build_pacing_plan(elevation_data, checkpoints, start.time(), target_seconds);
+    let checkpoints = plan.checkpoints.into_iter()
+        .map(|checkpoint| {
+            let arrival = add_seconds(start, checkpoint.elapsed_seconds).unwrap_or(finish);
+            let point = point_at(gpx_data, distances, checkpoint.distance_km * 1000.0);
+            CheckpointLight {
+                in_darkness: is_dark(arrival.with_timezone(&Utc), point.lat, point.lon),
+                name: checkpoint.name,
+                distance_km: checkpoint.distance_km,
+                arrival,
+            }
+        })
+        .collect();
+
+    Ok(DaylightPlan {
+        start,
+        finish,
+        target_seconds,
+        sun_times,
+        dark_distance_km: dark_sections.iter().map(|s| s.distance_km).sum(),
+        dark_sections,
+        checkpoints,
+    })
+}
+
+/// `at` moved on by `seconds`, or `None` past the range of dates
+fn add_seconds<Tz: TimeZone>(at: DateTime<Tz>, seconds: f64) -> Option<DateTime<Tz>> {
+    at.checked_add_signed(Duration::try_milliseconds((seconds * 1000.0).round() as i64)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::processing::ProcessingOptions;
+    use crate::core::models::race::GpxPoint;
+    use crate::core::services::pacing_plan::parse_duration;
+
+    #[test]
+    fn test_dark_sections_after_dusk() {
+        // ~10 km flat from Greenwich, run evenly over 4 hours from 17:00 UTC
+        // on the 2024 equinox; civil dusk is about 18:47
+        let gpx_data = GpxData {
+            points: (0..900)
+                .map(|i| GpxPoint { lat: 51.4769 + i as f64 * 0.0001, lon: 0.0, ele: 20.0, time: None })
+                .collect(),
+        };
+        let options = ProcessingOptions {
+            smoothed: false,
+            capping: false,
+            ..Default::default()
+        };
+        let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+        let start = DateTime::parse_from_rfc3339("2024-03-20T17:00:00+00:00").unwrap();
+
+        let plan = build_daylight_plan(&gpx_data, &elevation_data, &[], start, 4.0 * 3600.0).unwrap();
+
+        assert_eq!(plan.sun_times.len(), 1);
+        assert_eq!(plan.dark_sections.len(), 1);
+        let dark = &plan.dark_sections[0];
+        // 1h47 of 4h is ~45% of the way
+        let total_km = elevation_data.cumulative_distance.last().unwrap() / 1000.0;
+        assert!((dark.start_km / total_km - 0.45).abs() < 0.02, "{}", dark.start_km);
+        assert!((dark.end_km - total_km).abs() < 0.02);
+        assert!(plan.checkpoints.last().unwrap().in_darkness);
+    }
+
+    #[test]
+    fn test_finish_out_of_range() {
+        let gpx_data = GpxData {
+            points: (0..10)
+                .map(|i| GpxPoint { lat: 51.4769 + i as f64 * 0.0001, lon: 0.0, ele: 20.0, time: None })
+                .collect(),
+        };
+        let elevation_data = ElevationData::from_gpx_data(&gpx_data, &ProcessingOptions::default());
+        let start = DateTime::parse_from_rfc3339("2024-03-20T17:00:00+00:00").unwrap();
+
+        let target = parse_duration("4294967295:00").unwrap();
+        assert!(build_daylight_plan(&gpx_data, &elevation_data, &[], start, target).is_err());
+    }
+}
@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+
+use crate::core::models::race::{ElevationCorrectionMode, ElevationSource, GpxData};
+use crate::errors::handlers::ApiError;
+
+/// Void marker used by SRTM `.hgt` tiles
+const HGT_VOID: f64 = -32768.0;
+
+/// South-west corner of a 1°×1° tile, in whole degrees
+type TileKey = (i32, i32);
+
+/// A tile decoded at most once, however many lookups wait on it; `None` if it
+/// failed to load, in which case the slot is dropped so the next lookup retries
+type TileSlot = Arc<OnceLock<Option<Arc<DemTile>>>>;
+
+/// Elevation lookups against local SRTM (`.hgt`) and Copernicus (GeoTIFF) DEM tiles.
+///
+/// Tiles are 1°×1° and located by the `N46E007` / `N46_00_E007_00` pattern in
+/// their file names. Decoded tiles are kept in a least-recently-used cache
+/// bounded by their size in memory.
+pub struct DemService {
+    tiles: HashMap<TileKey, PathBuf>,
+    cache: Mutex<TileCache>,
+    cache_capacity_bytes: usize,
+}
+
+#[derive(Default)]
+struct TileCache {
+    slots: HashMap<TileKey, (TileSlot, u64)>,
+    /// Incremented on every lookup to order slots by last use
+    clock: u64,
+}
+
+/// Outcome of applying DEM elevations to a track
+#[derive(Debug, Clone)]
+pub struct DemCorrection {
+    pub gpx_data: GpxData,
+    pub source: ElevationSource,
+    pub points_corrected: usize,
+}
+
+struct DemTile {
+    /// Latitude and longitude of the first sample (north-west corner)
+    origin_lat: f64,
+    origin_lon: f64,
+    step_lat: f64,
+    step_lon: f64,
+    rows: usize,
+    cols: usize,
+    samples: Vec<f32>,
+    nodata: Option<f64>,
+}
+
+impl DemService {
+    /// Index the tiles available in `directory`
+    pub fn new(directory: &Path, cache_capacity_bytes: usize) -> std::io::Result<Self> {
+        let mut tiles = HashMap::new();
+
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            let extension = path.extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_ascii_lowercase());
+
+            if !matches!(extension.as_deref(), Some("hgt") | Some("tif") | Some("tiff")) {
+                continue;
+            }
+
+            let key = path.file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(tile_key_from_name);
+
+            if let Some(key) = key {
+                tiles.insert(key, path);
+            }
+        }
+
+        println!("DEM service indexed {} tiles in {}", tiles.len(), directory.display());
+
+        Ok(Self {
+            tiles,
+            cache: Mutex::new(TileCache::default()),
+            cache_capacity_bytes,
+        })
+    }
+
+    /// Interpolated elevation at a coordinate, if a tile covers it
+    pub fn elevation_at(&self, lat: f64, lon: f64) -> Option<f64> {
+        let key = (lat.floor() as i32, lon.floor() as i32);
+        let tile = self.tile(key)?;
+        tile.bilinear(lat, lon)
+    }
+
+    /// Replace or blend the elevations of a track with DEM values.
+    ///
+    /// Points outside DEM coverage keep their recorded elevation.
+    pub fn correct(&self, gpx_data: &GpxData, mode: ElevationCorrectionMode) -> Result<DemCorrection, ApiError> {
+        let mut corrected = gpx_data.clone();
+        let mut points_corrected = 0;
+
+        for point in corrected.points.iter_mut() {
+            if let Some(dem_ele) = self.elevation_at(point.lat, point.lon) {
+                point.ele = match mode {
+                    ElevationCorrectionMode::Replace => dem_ele,
+                    ElevationCorrectionMode::Blend { dem_weight } => {
+                        dem_weight * dem_ele + (1.0 - dem_weight) * point.ele
+                    }
+                };
+                points_corrected += 1;
+            }
+        }
+
+        if points_corrected == 0 {
+            return Err(ApiError::BadRequest("No DEM tiles cover this route".to_string()));
+        }
+
+        println!("DEM corrected {} of {} points", points_corrected, corrected.points.len());
+
+        let source = match mode {
+            ElevationCorrectionMode::Replace => ElevationSource::Dem,
+            ElevationCorrectionMode::Blend { .. } => ElevationSource::Blended,
+        };
+
+        Ok(DemCorrection {
+            gpx_data: corrected,
+            source,
+            points_corrected,
+        })
+    }
+
+    fn tile(&self, key: TileKey) -> Option<Arc<DemTile>> {
+        let path = self.tiles.get(&key)?;
+
+        let slot = {
+            let mut cache = self.cache.lock().unwrap();
+            cache.clock += 1;
+            let clock = cache.clock;
+            let entry = cache.slots.entry(key).or_default();
+            entry.1 = clock;
+            entry.0.clone()
+        };
+
+        // Decoded outside the cache lock; lookups of the same tile wait here
+        // instead of decoding it again
+        let mut loaded = false;
+        let tile = slot.get_or_init(|| {
+            loaded = true;
+            match DemTile::load(path, key) {
+                Ok(tile) => Some(Arc::new(tile)),
+                Err(e) => {
+                    println!("Failed to load DEM tile {}: {}", path.display(), e);
+                    None
+                }
+            }
+        }).clone();
+
+        if tile.is_none() {
+            // Lookups already waiting share the failure, later ones retry
+            let mut cache = self.cache.lock().unwrap();
+            if cache.slots.get(&key).is_some_and(|(current, _)| Arc::ptr_eq(current, &slot)) {
+                cache.slots.remove(&key);
+            }
+        } else if loaded {
+            self.evict(key);
+        }
+
+        tile
+    }
+
+    /// Drop least recently used tiles until the decoded ones fit the capacity,
+    /// always keeping `keep`
+    fn evict(&self, keep: TileKey) {
+        let mut cache = self.cache.lock().unwrap();
+        let size = |slot: &TileSlot| slot.get().and_then(|tile| tile.as_ref()).map_or(0, |tile| tile.size_bytes());
+        let mut total: usize = cache.slots.values().map(|(slot, _)| size(slot)).sum();
+
+        while total > self.cache_capacity_bytes {
+            let oldest = cache.slots.iter()
+                .filter(|(key, (slot, _))| **key != keep && size(slot) > 0)
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+            let Some(oldest) = oldest else { break };
+            if let Some((slot, _)) = cache.slots.remove(&oldest) {
+                total -= size(&slot);
+            }
+        }
+    }
+}
+
+impl DemTile {
+    fn load(path: &Path, key: TileKey) -> Result<Self, String> {
+        let is_hgt = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case("hgt"))
+            .unwrap_or(false);
+
+        if is_hgt {
+            Self::load_hgt(path, key)
+        } else {
+            Self::load_geotiff(path)
+        }
+    }
+
+    /// SRTM tiles: big-endian i16 grid, samples on the tile edges
+    fn load_hgt(path: &Path, (lat, lon): TileKey) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let size = ((bytes.len() / 2) as f64).sqrt() as usize;
+
+        if size < 2 || size * size * 2 != bytes.len() {
+            return Err(format!("unexpected .hgt size of {} bytes", bytes.len()));
+        }
+
+        let samples = bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_be_bytes([b[0], b[1]]) as f32)
+            .collect();
+        let step = 1.0 / (size - 1) as f64;
+
+        Ok(Self {
+            origin_lat: lat as f64 + 1.0,
+            origin_lon: lon as f64,
+            step_lat: step,
+            step_lon: step,
+            rows: size,
+            cols: size,
+            samples,
+            nodata: Some(HGT_VOID),
+        })
+    }
+
+    /// Copernicus tiles: single-band GeoTIFF georeferenced by tie point and pixel scale
+    fn load_geotiff(path: &Path) -> Result<Self, String> {
+        let file = fs::File::open(path).map_err(|e| e.to_string())?;
+        let mut decoder = Decoder::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+
+        let (cols, rows) = decoder.dimensions().map_err(|e| e.to_string())?;
+        let tiepoint = decoder.get_tag_f64_vec(Tag::ModelTiepointTag).map_err(|e| e.to_string())?;
+        let scale = decoder.get_tag_f64_vec(Tag::ModelPixelScaleTag).map_err(|e| e.to_string())?;
+        let nodata = decoder.get_tag_ascii_string(Tag::GdalNodata)
+            .ok()
+            .and_then(|s| s.trim_matches(char::from(0)).trim().parse().ok());
+
+        if tiepoint.len() < 6 || scale.len() < 2 {
+            return Err("missing GeoTIFF georeferencing tags".to_string());
+        }
+
+        let samples: Vec<f32> = match decoder.read_image().map_err(|e| e.to_string())? {
+            DecodingResult::F32(data) => data,
+            DecodingResult::F64(data) => data.into_iter().map(|v| v as f32).collect(),
+            DecodingResult::I16(data) => data.into_iter().map(|v| v as f32).collect(),
+            DecodingResult::U16(data) => data.into_iter().map(|v| v as f32).collect(),
+            DecodingResult::I32(data) => data.into_iter().map(|v| v as f32).collect(),
+            _ => return Err("unsupported GeoTIFF sample format".to_string()),
+        };
+
+        let (rows, cols) = (rows as usize, cols as usize);
+        if samples.len() != rows * cols {
+            return Err("multi-band GeoTIFF tiles are not supported".to_string());
+        }
+
+        // Tie point marks the outer corner of the first pixel; sample at pixel centres
+        Ok(Self {
+            origin_lat: tiepoint[4] + (tiepoint[1] - 0.5) * scale[1],
+            origin_lon: tiepoint[3] + (0.5 - tiepoint[0]) * scale[0],
+            step_lat: scale[1],
+            step_lon: scale[0],
+            rows,
+            cols,
+            samples,
+            nodata,
+        })
+    }
+
+    fn size_bytes(&self) -> usize {
+        self.samples.len() * std::mem::size_of::<f32>()
+    }
+
+    fn sample(&self, row: usize, col: usize) -> Option<f64> {
+        let value = self.samples[row * self.cols + col] as f64;
+
+        if !value.is_finite() || self.nodata == Some(value) || value <= -1000.0 {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    fn bilinear(&self, lat: f64, lon: f64) -> Option<f64> {
+        let row_f = ((self.origin_lat - lat) / self.step_lat).clamp(0.0, (self.rows - 1) as f64);
+        let col_f = ((lon - self.origin_lon) / self.step_lon).clamp(0.0, (self.cols - 1) as f64);
+
+        let row0 = row_f.floor() as usize;
+        let col0 = col_f.floor() as usize;
+        let row1 = (row0 + 1).min(self.rows - 1);
+        let col1 = (col0 + 1).min(self.cols - 1);
+        let dy = row_f - row0 as f64;
+        let dx = col_f - col0 as f64;
+
+        let corners = [
+            (row0, col0, (1.0 - dx) * (1.0 - dy)),
+            (row0, col1, dx * (1.0 - dy)),
+            (row1, col0, (1.0 - dx) * dy),
+            (row1, col1, dx * dy),
+        ];
+
+        // Renormalise over valid samples so voids next to the track don't drop the point
+        let mut weighted_sum = 0.0;
+        let mut weight_total = 0.0;
+        for (row, col, weight) in corners {
+            if let Some(value) = self.sample(row, col) {
+                weighted_sum += value * weight;
+                weight_total += weight;
+            }
+        }
+
+        if weight_total > 1e-9 {
+            Some(weighted_sum / weight_total)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse the south-west corner of a tile from names like `N46E007` or
+/// `Copernicus_DSM_COG_10_N46_00_E007_00_DEM`
+fn tile_key_from_name(name: &str) -> Option<TileKey> {
+    let upper = name.to_ascii_uppercase();
+    let bytes = upper.as_bytes();
+
+    for start in 0..bytes.len() {
+        let lat_sign = match bytes[start] {
+            b'N' => 1,
+            b'S' => -1,
+            _ => continue,
+        };
+
+        let lat_digits = digits_at(bytes, start + 1, 2);
+        let Some(lat) = lat_digits else { continue };
+
+        // Skip an optional "_00" minutes part before the longitude
+        let mut lon_start = start + 3;
+        if bytes.get(lon_start) == Some(&b'_') {
+            lon_start += 1;
+            while bytes.get(lon_start).map(|b| b.is_ascii_digit()).unwrap_or(false) {
+                lon_start += 1;
+            }
+            if bytes.get(lon_start) == Some(&b'_') {
+                lon_start += 1;
+            }
+        }
+
+        let lon_sign = match bytes.get(lon_start) {
+            Some(b'E') => 1,
+            Some(b'W') => -1,
+            _ => continue,
+        };
+
+        if let Some(lon) = digits_at(bytes, lon_start + 1, 3) {
+            return Some((lat_sign * lat, lon_sign * lon));
+        }
+    }
+
+    None
+}
+
+fn digits_at(bytes: &[u8], start: usize, count: usize) -> Option<i32> {
+    let digits = bytes.get(start..start + count)?;
+    if !digits.iter().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    std::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_names() {
+        assert_eq!(tile_key_from_name("N46E007"), Some((46, 7)));
+        assert_eq!(tile_key_from_name("S34W071"), Some((-34, -71)));
+        assert_eq!(
+            tile_key_from_name("Copernicus_DSM_COG_10_N37_00_E127_00_DEM"),
+            Some((37, 127))
+        );
+        assert_eq!(tile_key_from_name("readme"), None);
+    }
+
+    #[test]
+    fn test_bilinear_interpolation() {
+        let tile = DemTile {
+            origin_lat: 47.0,
+            origin_lon: 7.0,
+            step_lat: 1.0,
+            step_lon: 1.0,
+            rows: 2,
+            cols: 2,
+            // North-west, north-east, south-west, south-east
+            samples: vec![1000.0, 2000.0, 0.0, HGT_VOID as f32],
+            nodata: Some(HGT_VOID),
+        };
+
+        assert_eq!(tile.bilinear(47.0, 7.0), Some(1000.0));
+        assert_eq!(tile.bilinear(47.0, 7.5), Some(1500.0));
+        // The void south-east corner is ignored rather than dragging the value down
+        let centre = tile.bilinear(46.5, 7.5).unwrap();
+        assert!((centre - 1000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_failed_tile_retried() {
+        let directory = std::env::temp_dir().join(format!("dem_retry_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("N46E007.hgt");
+
+        // Caught halfway through a copy
+        fs::write(&path, [0u8; 3]).unwrap();
+        let dem = DemService::new(&directory, 1 << 20).unwrap();
+        assert!(dem.tile((46, 7)).is_none());
+
+        // A 2×2 grid at 1000 m
+        fs::write(&path, [0x03, 0xe8, 0x03, 0xe8, 0x03, 0xe8, 0x03, 0xe8]).unwrap();
+        let tile = dem.tile((46, 7));
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(tile.unwrap().bilinear(46.5, 7.5), Some(1000.0));
+    }
+}
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::services::elevation_processor::ElevationData;
+use crate::core::services::itra_calculator::calculate_itra_effort;
+
+/// Effort of a course expressed in a common currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffortEstimate {
+    pub model: EffortModelKind,
+    /// Flat distance in km that takes the same effort
+    pub effort_distance_km: f64,
+    /// Moving time in hours, for models that predict one
+    pub moving_time_hours: Option<f64>,
+}
+
+/// A way of turning a processed elevation profile into effort
+pub trait EffortModel {
+    fn kind(&self) -> EffortModelKind;
+
+    fn estimate(&self, elevation_data: &ElevationData) -> EffortEstimate;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffortModelKind {
+    #[default]
+    Itra,
+    NaismithLangmuir,
+    Tobler,
+    Minetti,
+}
+
+impl EffortModelKind {
+    pub fn model(&self) -> &'static dyn EffortModel {
+        match self {
+            EffortModelKind::Itra => &ItraEffort,
+            EffortModelKind::NaismithLangmuir => &NaismithLangmuir,
+            EffortModelKind::Tobler => &Tobler,
+            EffortModelKind::Minetti => &Minetti,
+        }
+    }
+}
+
+/// ITRA effort points: km plus one per 100 m of ascent; descent is ignored
+pub struct ItraEffort;
+
+impl EffortModel for ItraEffort {
+    fn kind(&self) -> EffortModelKind {
+        EffortModelKind::Itra
+    }
+
+    fn estimate(&self, elevation_data: &ElevationData) -> EffortEstimate {
+        let distance_km = elevation_data.cumulative_distance.last().copied().unwrap_or(0.0) / 1000.0;
+        let gain_m = elevation_data.accumulated_ascent.last().copied().unwrap_or(0.0);
+
+        EffortEstimate {
+            model: self.kind(),
+            effort_distance_km: calculate_itra_effort(distance_km, gain_m),
+            moving_time_hours: None,
+        }
+    }
+}
+
+/// Naismith's rule (5 km/h plus 1 h per 600 m of ascent) with Langmuir's
+/// descent corrections: 10 min saved per 300 m on gentle descents (5–12°),
+/// 10 min added per 300 m on steep ones (over 12°)
+pub struct NaismithLangmuir;
+
+const NAISMITH_FLAT_KMH: f64 = 5.0;
+const NAISMITH_ASCENT_M_PER_HOUR: f64 = 600.0;
+const LANGMUIR_HOURS_PER_M: f64 = 10.0 / 60.0 / 300.0;
+
+impl EffortModel for NaismithLangmuir {
+    fn kind(&self) -> EffortModelKind {
+        EffortModelKind::NaismithLangmuir
+    }
+
+    fn estimate(&self, elevation_data: &ElevationData) -> EffortEstimate {
+        let gentle = 5f64.to_radians().tan() * 100.0;
+        let steep = 12f64.to_radians().tan() * 100.0;
+
+        let mut hours = 0.0;
+        for (distance_m, change_m) in segments(elevation_data) {
+            hours += distance_m / 1000.0 / NAISMITH_FLAT_KMH;
+
+            let gradient = change_m / distance_m * 100.0;
+            if change_m > 0.0 {
+                hours += change_m / NAISMITH_ASCENT_M_PER_HOUR;
+            } else if -gradient > steep {
+                hours -= change_m * LANGMUIR_HOURS_PER_M;
+            } else if -gradient >= gentle {
+                hours += change_m * LANGMUIR_HOURS_PER_M;
+            }
+        }
+
+        EffortEstimate {
+            model: self.kind(),
+            effort_distance_km: hours * NAISMITH_FLAT_KMH,
+            moving_time_hours: Some(hours),
+        }
+    }
+}
+
+/// Tobler's hiking function: 6·e^(−3.5·|slope + 0.05|) km/h
+pub struct Tobler;
+
+fn tobler_speed_kmh(slope: f64) -> f64 {
+    6.0 * (-3.5 * (slope + 0.05).abs()).exp()
+}
+
+impl EffortModel for Tobler {
+    fn kind(&self) -> EffortModelKind {
+        EffortModelKind::Tobler
+    }
+
+    fn estimate(&self, elevation_data: &ElevationData) -> EffortEstimate {
+        let hours: f64 = segments(elevation_data)
+            .map(|(distance_m, change_m)| distance_m / 1000.0 / tobler_speed_kmh(change_m / distance_m))
+            .sum();
+
+        EffortEstimate {
+            model: self.kind(),
+            effort_distance_km: hours * tobler_speed_kmh(0.0),
+            moving_time_hours: Some(hours),
+        }
+    }
+}
+
+/// Minetti et al. (2002) energy cost of running on a gradient, relative to
+/// the flat; no time, since that depends on the runner
+pub struct Minetti;
+
+/// Energy cost of running in J/kg/m at gradient `slope` (rise over run)
+pub fn minetti_cost(slope: f64) -> f64 {
+    // The polynomial was fitted between −45% and +45%
+    let i = slope.clamp(-0.45, 0.45);
+    155.4 * i.powi(5) - 30.4 * i.powi(4) - 43.3 * i.powi(3) + 46.3 * i.powi(2) + 19.5 * i + 3.6
+}
+
+impl EffortModel for Minetti {
+    fn kind(&self) -> EffortModelKind {
+        EffortModelKind::Minetti
+    }
+
+    fn estimate(&self, elevation_data: &ElevationData) -> EffortEstimate {
+        let flat_cost = minetti_cost(0.0);
+        let effort_m: f64 = segments(elevation_data)
+            .map(|(distance_m, change_m)| distance_m * minetti_cost(change_m / distance_m) / flat_cost)
+            .sum();
+
+        EffortEstimate {
+            model: self.kind(),
+            effort_distance_km: effort_m / 1000.0,
+            moving_time_hours: None,
+        }
+    }
+}
+
+/// Distance and elevation change of each step of the processed profile
+fn segments(elevation_data: &ElevationData) -> impl Iterator<Item = (f64, f64)> + '_ {
+    elevation_data.distance_change.iter()
+        .zip(&elevation_data.altitude_change)
+        .map(|(distance, change)| (*distance, *change))
+        .filter(|(distance, _)| *distance > 0.0)
+}
+
+/// Similarity of two effort distances (1.0 = perfect match, 0.0 = no similarity)
+pub fn effort_similarity(reference_km: f64, candidate_km: f64) -> f64 {
+    if reference_km <= 0.0 {
+        return 0.0;
+    }
+    let diff = (reference_km - candidate_km).abs() / reference_km;
+
+    // Exponential decay for smoother scoring
+    (-diff * 5.0).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    /// 10 km out and back over a 500 m hill
+    fn out_and_back(grade: f64) -> ElevationData {
+        straight_course(900, |d| if d < 5000.0 { d * grade } else { (10_000.0 - d) * grade }).1
+    }
+
+    #[test]
+    fn test_models_agree_on_the_flat() {
+        let flat = out_and_back(0.0);
+        let distance_km = flat.cumulative_distance.last().unwrap() / 1000.0;
+
+        for kind in [EffortModelKind::Itra, EffortModelKind::NaismithLangmuir, EffortModelKind::Tobler, EffortModelKind::Minetti] {
+            let estimate = kind.model().estimate(&flat);
+            assert!((estimate.effort_distance_km - distance_km).abs() < 0.01, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_descent_counts_outside_itra() {
+        // 10% is gentle enough (5.7°) for Langmuir's descent bonus
+        let hill = out_and_back(0.1);
+        let distance_km = hill.cumulative_distance.last().unwrap() / 1000.0;
+
+        let itra = ItraEffort.estimate(&hill);
+        let naismith = NaismithLangmuir.estimate(&hill);
+        let minetti = Minetti.estimate(&hill);
+
+        let naismith_hours = naismith.moving_time_hours.unwrap();
+        let expected = distance_km / 5.0 + 500.0 / 600.0 - 500.0 / 300.0 * 10.0 / 60.0;
+        assert!((naismith_hours - expected).abs() < 0.05);
+
+        // Running downhill is cheaper than the flat, so Minetti lands between
+        // the flat distance and ITRA's ascent-only figure
+        assert!(minetti.effort_distance_km > distance_km);
+        assert!(minetti.effort_distance_km < itra.effort_distance_km);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::core::algorithms::smoothing::{smooth, SmoothingMethod};
+use crate::core::models::processing::{CappingThreshold, ProcessingOptions};
 use crate::core::models::race::GpxData;
 
 #[derive(Debug, Clone)]
@@ -13,10 +15,14 @@ pub struct ElevationData {
     pub descent: Vec<f64>,
     pub overall_uphill_gradient: f64,
     pub overall_downhill_gradient: f64,
+    /// Whether smoothing actually ran (it is skipped on very hilly routes)
+    pub smoothing_applied: bool,
+    /// First point of `processed_altitude`, which smoothing may move
+    processed_start: f64,
 }
 
 impl ElevationData {
-    pub fn from_gpx_data(gpx_data: &GpxData, smooth: bool) -> Self {
+    pub fn from_gpx_data(gpx_data: &GpxData, options: &ProcessingOptions) -> Self {
         let mut enhanced_altitude = Vec::new();
         let mut cumulative_distance = Vec::new();
         let mut current_distance = 0.0;
@@ -48,14 +54,16 @@ impl ElevationData {
             descent: vec![],
             overall_uphill_gradient: 0.0,
             overall_downhill_gradient: 0.0,
+            smoothing_applied: false,
+            processed_start: gpx_data.points.first().map(|p| p.ele).unwrap_or(0.0),
         };
         
         // Calculate distance changes
         data.calculate_distance_changes();
         
-        // Process elevation data with or without smoothing
-        if smooth {
-            data.process_elevation_data();
+        // Process elevation data, or leave it raw when neither smoothing nor capping is wanted
+        if options.smoothed || options.capping {
+            data.process_elevation_data(options);
         } else {
             data.process_raw_elevation_data();
         }
@@ -143,49 +151,31 @@ impl ElevationData {
         }
     }
     
-    fn rolling_mean(data: &[f64], window: usize) -> Vec<f64> {
-        let mut result = vec![];
-        
-        for i in 0..data.len() {
-            let start = if i >= window { i - window + 1 } else { 0 };
-            let end = i + 1;
-            
-            let sum: f64 = data[start..end].iter().sum();
-            let count = end - start;
-            
-            result.push(sum / count as f64);
-        }
-        
-        result
-    }
-    
-    fn apply_gradient_smoothing(&mut self) {
+    fn apply_gradient_smoothing(&mut self, method: &SmoothingMethod, max_hilliness: f64) {
         let hilliness_ratio = self.overall_uphill_gradient;
         
-        // Apply rolling mean smoothing only if hilliness ratio is below 20m/km
-        // Use 100m window for smoothing
-        if hilliness_ratio < 20.0 {
-            self.altitude_change = Self::rolling_mean(&self.altitude_change, 100);
+        // Apply smoothing only below the sport's hilliness limit (20m/km by default).
+        // Windows are in metres, so the result doesn't depend on point spacing.
+        // The recorded altitude is kept; the smoothed one is `processed_altitude`
+        if hilliness_ratio < max_hilliness {
+            let smoothed = smooth(&self.cumulative_distance, &self.enhanced_altitude, method);
+            self.processed_start = smoothed.first().copied().unwrap_or(0.0);
+            self.altitude_change = std::iter::once(0.0)
+                .chain(smoothed.windows(2).map(|pair| pair[1] - pair[0]))
+                .collect();
             self.calculate_gradients();
+            self.smoothing_applied = true;
         }
     }
     
-    fn apply_gradient_capping(&mut self) {
+    fn apply_gradient_capping(&mut self, thresholds: &[CappingThreshold]) {
         let hilliness_ratio = self.overall_uphill_gradient;
         
-        // Define thresholds: (limit, max positive gradient, max negative gradient)
-        let thresholds = vec![
-            (20.0, 15.0, 12.0),
-            (30.0, 20.0, 15.0),
-            (40.0, 25.0, 20.0),
-            (50.0, 32.0, 27.0),
-            (60.0, 35.0, 31.0),
-            (f64::INFINITY, 40.0, 36.0),
-        ];
-        
-        // Apply capping for hillier routes (20m/km and above)
-        for (limit, max_up, max_down) in thresholds {
-            if hilliness_ratio < limit {
+        // The sport's table gives (limit, max positive gradient, max negative gradient);
+        // the first band the route's hilliness falls under applies
+        for threshold in thresholds {
+            let (max_up, max_down) = (threshold.max_up, threshold.max_down);
+            if hilliness_ratio < threshold.hilliness_limit {
                 for i in 0..self.gradient_percent.len() {
                     if self.gradient_percent[i] > max_up {
                         self.altitude_change[i] = max_up * self.distance_change[i] / 100.0;
@@ -237,7 +227,7 @@ impl ElevationData {
         }
     }
     
-    pub fn process_elevation_data(&mut self) {
+    pub fn process_elevation_data(&mut self, options: &ProcessingOptions) {
         // Step 1: Calculate initial altitude changes
         self.calculate_altitude_changes();
         
@@ -251,10 +241,14 @@ impl ElevationData {
         self.calculate_overall_gradients();
         
         // Step 5: Apply smoothing if applicable
-        self.apply_gradient_smoothing();
+        if options.smoothed {
+            self.apply_gradient_smoothing(&options.smoothing, options.smoothing_max_hilliness);
+        }
         
         // Step 6: Apply gradient capping based on terrain type
-        self.apply_gradient_capping();
+        if options.capping {
+            self.apply_gradient_capping(&options.capping_thresholds);
+        }
         
         // Step 7: Separate into ascent and descent
         self.separate_ascent_descent();
@@ -278,6 +272,17 @@ impl ElevationData {
         self.calculate_overall_gradients();
         self.separate_ascent_descent();
     }
+    
+    /// Elevation profile rebuilt from the processed altitude changes, so
+    /// smoothing and capping are reflected in every point
+    pub fn processed_altitude(&self) -> Vec<f64> {
+        self.altitude_change.iter()
+            .scan(self.processed_start, |altitude, change| {
+                *altitude += change;
+                Some(*altitude)
+            })
+            .collect()
+    }
 }
 
 fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
@@ -292,3 +297,38 @@ fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     
     r * c
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::race::GpxPoint;
+
+    #[test]
+    fn test_smoothing_keeps_recorded_altitude() {
+        // Gentle climb with a 2 m GPS step every ~110 m
+        let gpx_data = GpxData {
+            points: (0..200)
+                .map(|i| GpxPoint {
+                    lat: 46.0 + i as f64 * 0.0001,
+                    lon: 7.0,
+                    ele: 500.0 + i as f64 * 0.1 + if (i / 10) % 2 == 0 { 1.0 } else { -1.0 },
+                    time: None,
+                })
+                .collect(),
+        };
+        let options = ProcessingOptions {
+            smoothed: true,
+            capping: false,
+            ..Default::default()
+        };
+        let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+
+        assert!(elevation_data.smoothing_applied);
+        assert!(elevation_data.enhanced_altitude.iter().zip(&gpx_data.points).all(|(a, p)| *a == p.ele));
+
+        let processed = elevation_data.processed_altitude();
+        let biggest_step = processed.windows(2).map(|pair| (pair[1] - pair[0]).abs()).fold(0.0, f64::max);
+        assert!(biggest_step < 1.0, "{}", biggest_step);
+        assert!((processed[100] - 510.0).abs() < 1.5, "{}", processed[100]);
+    }
+}
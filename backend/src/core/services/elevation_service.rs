@@ -1,5 +1,8 @@
 use crate::core::models::race::{GpxData, ElevationProfile, GradientDistribution, GradientBin};
-use crate::core::algorithms::gradient_analysis::{analyze_gradients, categorize_gradients};
+use crate::core::algorithms::gradient_analysis::{analyze_gradients, categorize_gradients, GradientBins};
+use crate::core::algorithms::smoothing::{moving_average, SmoothingMethod};
+use crate::core::models::processing::ProcessingOptions;
+use crate::core::services::elevation_processor::ElevationData;
 
 pub fn calculate_elevation_metrics(gpx_data: &GpxData) -> (f64, f64, f64) {
     println!("=== CALCULATING ELEVATION METRICS ===");
@@ -63,9 +66,10 @@ pub fn calculate_elevation_profile(gpx_data: &GpxData, window_size: u32) -> Elev
         elevations.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
     );
     
-    // Apply smoothing if window_size > 0
+    // Apply smoothing if window_size > 0, with the window in metres
     let smoothed_elevations = if window_size > 0 {
-        smooth_elevations(&elevations, window_size as usize)
+        let distances_m: Vec<f64> = distances.iter().map(|d| d * 1000.0).collect();
+        moving_average(&distances_m, &elevations, window_size as f64, true)
     } else {
         elevations.clone()
     };
@@ -77,68 +81,76 @@ pub fn calculate_elevation_profile(gpx_data: &GpxData, window_size: u32) -> Elev
         elevation: smoothed_elevations,
         smoothed: window_size > 0,
         window_size,
+        smoothing_applied: window_size > 0,
+        options: ProcessingOptions {
+            smoothed: window_size > 0,
+            smoothing: SmoothingMethod::MovingAverage {
+                window_m: window_size as f64,
+                centered: true,
+            },
+            capping: false,
+            ..Default::default()
+        },
+        min_elevation: None,
+        max_elevation: None,
+        spacing_m: None,
     }
 }
 
 pub fn calculate_gradient_distribution(
     gpx_data: &GpxData,
-    window_size: u32,
+    options: &ProcessingOptions,
+    bins: &GradientBins,
 ) -> GradientDistribution {
     println!("=== CALCULATING GRADIENT DISTRIBUTION ===");
     
-    // Use the gradient analysis algorithm
-    let segments = analyze_gradients(gpx_data, window_size as f64);
+    // Use the gradient analysis algorithm on the processed elevations
+    let elevation_data = ElevationData::from_gpx_data(gpx_data, options);
+    let segments = analyze_gradients(&elevation_data, options.gradient_window_m);
     println!("Analyzed {} gradient segments", segments.len());
     
-    let (ascent_bins, descent_bins) = categorize_gradients(&segments);
+    let categories = categorize_gradients(&segments, bins);
     
-    let total_distance = calculate_elevation_metrics(gpx_data).0;
+    let total_ascent: f64 = categories.ascent_m.iter().sum();
+    let total_descent: f64 = categories.descent_m.iter().sum();
+    let total_distance = total_ascent + total_descent + categories.flat_m;
     
-    let ascent: Vec<GradientBin> = ascent_bins.into_iter()
-        .map(|(range, percentage)| {
-            let distance = (percentage / 100.0) * total_distance;
-            println!("Ascent bin {}: {:.1}% ({:.2}km)", range, percentage, distance);
-            
-            GradientBin {
-                range,
-                percentage,
-                distance,
-            }
+    let to_bin = |range: String, distance_m: f64, total_m: f64| GradientBin {
+        range,
+        percentage: if total_m > 0.0 { distance_m / total_m * 100.0 } else { 0.0 },
+        distance: distance_m / 1000.0,
+    };
+    
+    let ascent: Vec<GradientBin> = categories.labels.iter()
+        .zip(&categories.ascent_m)
+        .map(|(range, distance_m)| {
+            let bin = to_bin(range.clone(), *distance_m, total_ascent);
+            println!("Ascent bin {}: {:.1}% ({:.2}km)", bin.range, bin.percentage, bin.distance);
+            bin
         })
         .collect();
     
-    let descent: Vec<GradientBin> = descent_bins.into_iter()
-        .map(|(range, percentage)| {
-            let distance = (percentage / 100.0) * total_distance;
-            println!("Descent bin {}: {:.1}% ({:.2}km)", range, percentage, distance);
-            
-            GradientBin {
-                range,
-                percentage,
-                distance,
-            }
+    let descent: Vec<GradientBin> = categories.labels.iter()
+        .zip(&categories.descent_m)
+        .map(|(range, distance_m)| {
+            let bin = to_bin(range.clone(), *distance_m, total_descent);
+            println!("Descent bin {}: {:.1}% ({:.2}km)", bin.range, bin.percentage, bin.distance);
+            bin
         })
         .collect();
     
-    println!("=== GRADIENT DISTRIBUTION COMPLETE ===");
+    let flat = to_bin(format!("0-{}", bins.flat_threshold), categories.flat_m, total_distance);
+    println!("Flat: {:.1}% ({:.2}km)", flat.percentage, flat.distance);
     
-    GradientDistribution { ascent, descent }
-}
-
-fn smooth_elevations(elevations: &[f64], window_size: usize) -> Vec<f64> {
-    let half_window = window_size / 2;
-    let mut smoothed = Vec::with_capacity(elevations.len());
+    println!("=== GRADIENT DISTRIBUTION COMPLETE ===");
     
-    for i in 0..elevations.len() {
-        let start = i.saturating_sub(half_window);
-        let end = (i + half_window + 1).min(elevations.len());
-        
-        let sum: f64 = elevations[start..end].iter().sum();
-        let avg = sum / (end - start) as f64;
-        smoothed.push(avg);
+    GradientDistribution {
+        ascent,
+        descent,
+        flat,
+        bins: bins.clone(),
+        options: options.clone(),
     }
-    
-    smoothed
 }
 
 fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
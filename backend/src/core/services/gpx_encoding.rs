@@ -0,0 +1,156 @@
+use encoding_rs::{CoderResult, Decoder, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+use crate::errors::handlers::ApiError;
+
+/// Number of leading bytes inspected when looking for the XML declaration
+const DECLARATION_SCAN_BYTES: usize = 512;
+
+/// Incremental decoder turning uploaded GPX bytes into UTF-8 text.
+///
+/// The encoding is detected once enough leading bytes have arrived (or the
+/// input ends), after which every chunk is transcoded as it comes in.
+#[derive(Default)]
+pub struct GpxDecoder {
+    pending: Vec<u8>,
+    decoder: Option<Decoder>,
+}
+
+impl GpxDecoder {
+    /// Decode a chunk of input, appending the text to `output`
+    pub fn decode(&mut self, chunk: &[u8], output: &mut String, last: bool) -> Result<(), ApiError> {
+        if self.decoder.is_none() {
+            self.pending.extend_from_slice(chunk);
+
+            if self.pending.len() < DECLARATION_SCAN_BYTES && !last {
+                return Ok(());
+            }
+
+            let (encoding, bom_length) = detect_encoding(&self.pending);
+            println!("Detected GPX encoding: {}", encoding.name());
+
+            self.decoder = Some(encoding.new_decoder_without_bom_handling());
+            let pending = std::mem::take(&mut self.pending);
+            return self.transcode(&pending[bom_length..], output, last);
+        }
+
+        self.transcode(chunk, output, last)
+    }
+
+    fn transcode(&mut self, mut input: &[u8], output: &mut String, last: bool) -> Result<(), ApiError> {
+        let decoder = self.decoder.as_mut().expect("encoding detected before transcoding");
+
+        loop {
+            let needed = decoder.max_utf8_buffer_length(input.len()).unwrap_or(input.len() * 3);
+            output.reserve(needed);
+
+            let (result, read, had_errors) = decoder.decode_to_string(input, output, last);
+            if had_errors {
+                return Err(ApiError::BadRequest(format!(
+                    "Invalid {} in GPX file",
+                    decoder.encoding().name()
+                )));
+            }
+
+            input = &input[read..];
+            match result {
+                CoderResult::InputEmpty => return Ok(()),
+                CoderResult::OutputFull => continue,
+            }
+        }
+    }
+}
+
+/// Detect the encoding of a GPX document from its first bytes.
+///
+/// Returns the encoding and the length of the byte order mark to skip.
+/// A BOM takes precedence, then the byte pattern of a BOM-less UTF-16
+/// declaration, then the `encoding` attribute of the XML declaration.
+pub fn detect_encoding(prefix: &[u8]) -> (&'static Encoding, usize) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(prefix) {
+        return (encoding, bom_length);
+    }
+
+    // "<?" encoded as UTF-16 without a BOM
+    if prefix.starts_with(&[0x3C, 0x00, 0x3F, 0x00]) {
+        return (UTF_16LE, 0);
+    }
+    if prefix.starts_with(&[0x00, 0x3C, 0x00, 0x3F]) {
+        return (UTF_16BE, 0);
+    }
+
+    let encoding = declared_encoding(prefix)
+        // A UTF-16 label cannot be right for a document readable as ASCII
+        .filter(|encoding| *encoding != UTF_16LE && *encoding != UTF_16BE)
+        .unwrap_or(UTF_8);
+
+    (encoding, 0)
+}
+
+/// Read the `encoding` attribute from an ASCII-compatible XML declaration
+fn declared_encoding(prefix: &[u8]) -> Option<&'static Encoding> {
+    let scan = &prefix[..prefix.len().min(DECLARATION_SCAN_BYTES)];
+
+    if !scan.starts_with(b"<?xml") {
+        return None;
+    }
+
+    let end = scan.windows(2).position(|w| w == b"?>")?;
+    let declaration = std::str::from_utf8(&scan[..end]).ok()?;
+    let label = attribute_value(declaration, "encoding")?;
+
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+fn attribute_value<'a>(declaration: &'a str, name: &str) -> Option<&'a str> {
+    let start = declaration.find(name)? + name.len();
+    let rest = declaration[start..].trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &rest[1..];
+    let end = value.find(quote)?;
+
+    Some(&value[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::EUC_KR;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="EUC-KR"?><gpx><trk><name>서울 트레일</name></trk></gpx>"#;
+
+    fn decode_in_chunks(bytes: &[u8], chunk_size: usize) -> Result<String, ApiError> {
+        let mut decoder = GpxDecoder::default();
+        let mut output = String::new();
+
+        for chunk in bytes.chunks(chunk_size) {
+            decoder.decode(chunk, &mut output, false)?;
+        }
+        decoder.decode(&[], &mut output, true)?;
+
+        Ok(output)
+    }
+
+    #[test]
+    fn test_utf16_with_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in GPX.encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        assert_eq!(detect_encoding(&bytes), (UTF_16LE, 2));
+
+        let decoded = decode_in_chunks(&bytes, 7).unwrap();
+        assert_eq!(decoded, GPX);
+    }
+
+    #[test]
+    fn test_declared_euc_kr() {
+        let (bytes, _, _) = EUC_KR.encode(GPX);
+        assert!(String::from_utf8(bytes.to_vec()).is_err());
+
+        assert_eq!(detect_encoding(&bytes), (EUC_KR, 0));
+
+        let decoded = decode_in_chunks(&bytes, 5).unwrap();
+        assert!(decoded.contains("서울 트레일"));
+    }
+}
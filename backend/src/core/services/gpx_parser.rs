@@ -1,184 +1,370 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::algorithms::track_repair::{repair_track, CorrectionKind, TrackCorrection, TrackRepairOptions};
 use crate::core::models::race::{GpxData, GpxPoint};
+use crate::core::services::gpx_encoding::GpxDecoder;
 use crate::errors::handlers::ApiError;
 
 /// Maximum number of points to process (to prevent memory issues)
-const MAX_POINTS: usize = 50000;
+pub(crate) const MAX_POINTS: usize = 50000;
 
 /// Minimum distance between points in meters (to reduce density)
 const MIN_DISTANCE_METERS: f64 = 5.0;
 
-pub fn parse_gpx(gpx_content: &str) -> Result<GpxData, ApiError> {
-    println!("=== PARSING GPX - START ===");
-    println!("GPX content length: {} bytes", gpx_content.len());
-    
-    // Clean up the GPX content to handle extended namespaces
-    let cleaned_content = clean_gpx_content(gpx_content);
-    
-    // Extract raw points from GPX
-    let raw_points = extract_raw_points(&cleaned_content)?;
-    
-    if raw_points.is_empty() {
-        return Err(ApiError::BadRequest("No valid track points found in GPX file".to_string()));
-    }
-    
-    println!("Raw points extracted: {}", raw_points.len());
-    
-    // Strip and optimize points
-    let stripped_points = strip_and_optimize_points(raw_points)?;
-    
-    println!("Points after stripping: {}", stripped_points.len());
-    println!("=== PARSING GPX - SUCCESS ===");
-    
-    Ok(GpxData { points: stripped_points })
+/// Largest unparsed text the stream parser may hold while waiting for an element to close
+const MAX_PENDING_TEXT: usize = 1_048_576;
+
+/// What the import found in a file and every fix it made to the track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportDiagnostics {
+    pub bytes_read: usize,
+    /// Points in the file, before thinning
+    pub raw_points: usize,
+    /// The file had no track, so its waypoints were used
+    pub from_waypoints: bool,
+    pub has_timestamps: bool,
+    /// Points stored after thinning and repair
+    pub points: usize,
+    /// Track length through every point in the file, and as stored
+    pub raw_distance_km: f64,
+    pub distance_km: f64,
+    pub corrections: Vec<TrackCorrection>,
 }
 
-/// Extract raw points from GPX content
-fn extract_raw_points(content: &str) -> Result<Vec<GpxPoint>, ApiError> {
-    let mut points = Vec::new();
-    
-    // Try to parse using the gpx crate first
-    match gpx::read(content.as_bytes()) {
-        Ok(gpx) => {
-            // Extract from tracks
-            for track in &gpx.tracks {
-                for segment in &track.segments {
-                    for point in &segment.points {
-                        points.push(GpxPoint {
-                            lat: point.point().y(),
-                            lon: point.point().x(),
-                            ele: point.elevation.unwrap_or(0.0),
-                            time: None, // Strip time data
-                        });
-                    }
+/// Incremental GPX parser fed with raw upload chunks.
+///
+/// Bytes are transcoded as they arrive and complete `<trkpt>`/`<wpt>`
+/// elements are consumed immediately, so memory is bounded by the thinned
+/// point list rather than by the size of the file.
+pub struct GpxStreamParser {
+    decoder: GpxDecoder,
+    text: String,
+    track_points: PointThinner,
+    waypoints: PointThinner,
+    bytes_read: usize,
+}
+
+impl GpxStreamParser {
+    pub fn new() -> Self {
+        println!("=== PARSING GPX - START ===");
+
+        Self {
+            decoder: GpxDecoder::default(),
+            text: String::new(),
+            track_points: PointThinner::default(),
+            waypoints: PointThinner::default(),
+            bytes_read: 0,
+        }
+    }
+
+    /// Feed the next chunk of the uploaded file
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), ApiError> {
+        self.bytes_read += chunk.len();
+        self.decoder.decode(chunk, &mut self.text, false)?;
+        self.consume_elements()
+    }
+
+    /// Finish parsing and return the repaired, stripped and optimized points
+    pub fn finish(mut self, repair: &TrackRepairOptions) -> Result<(GpxData, ImportDiagnostics), ApiError> {
+        self.decoder.decode(&[], &mut self.text, true)?;
+        self.consume_elements()?;
+
+        println!("GPX content length: {} bytes", self.bytes_read);
+        println!(
+            "Raw points extracted: {} track points, {} waypoints",
+            self.track_points.seen, self.waypoints.seen
+        );
+
+        // If no tracks, fall back to waypoints
+        let from_waypoints = self.track_points.seen == 0;
+        let thinner = if self.track_points.seen > 0 {
+            self.track_points
+        } else {
+            self.waypoints
+        };
+
+        if thinner.seen == 0 {
+            return Err(ApiError::BadRequest("No valid track points found in GPX file".to_string()));
+        }
+
+        let raw_points = thinner.seen;
+        let raw_distance_km = thinner.raw_distance_km;
+        let mut points = thinner.finish()?;
+        let has_timestamps = points.iter().any(|p| p.time.is_some());
+
+        // Repaired at the density the track was recorded at (bar the thinning),
+        // and only then limited in size. Timestamps are only kept this far, to
+        // judge speeds
+        let mut corrections = repair_track(&mut points, repair);
+        if points.len() > MAX_POINTS {
+            println!("Reducing points from {} to {}", points.len(), MAX_POINTS);
+            points = downsample_points(points, MAX_POINTS);
+        }
+        corrections.extend(clean_elevation_data(&mut points));
+        for point in points.iter_mut() {
+            point.time = None;
+        }
+
+        if points.len() < 2 {
+            return Err(ApiError::BadRequest("Insufficient valid points after repair".to_string()));
+        }
+
+        println!("Points after stripping: {}", points.len());
+        println!("Track corrections: {}", corrections.len());
+        println!("=== PARSING GPX - SUCCESS ===");
+
+        let diagnostics = ImportDiagnostics {
+            bytes_read: self.bytes_read,
+            raw_points,
+            from_waypoints,
+            has_timestamps,
+            points: points.len(),
+            raw_distance_km,
+            distance_km: track_length_km(&points),
+            corrections,
+        };
+
+        Ok((GpxData { points }, diagnostics))
+    }
+
+    /// Consume every complete point element in the buffered text
+    fn consume_elements(&mut self) -> Result<(), ApiError> {
+        let mut position = 0;
+
+        while let Some(offset) = self.text[position..].find('<') {
+            let start = position + offset;
+            let rest = &self.text[start..];
+
+            if rest.starts_with("<!--") {
+                match rest.find("-->") {
+                    Some(end) => position = start + end + 3,
+                    None => break,
                 }
+                continue;
             }
-            
-            // If no tracks, try waypoints
-            if points.is_empty() {
-                for waypoint in &gpx.waypoints {
-                    points.push(GpxPoint {
-                        lat: waypoint.point().y(),
-                        lon: waypoint.point().x(),
-                        ele: waypoint.elevation.unwrap_or(0.0),
-                        time: None,
-                    });
+
+            if rest.starts_with("<![CDATA[") {
+                match rest.find("]]>") {
+                    Some(end) => position = start + end + 3,
+                    None => break,
                 }
+                continue;
             }
-        }
-        Err(_) => {
-            // Fallback to regex parsing
-            println!("Using fallback regex parsing...");
-            
-            // Parse track points
-            let trkpt_regex = regex::Regex::new(
-                r#"<trkpt\s+lat="([^"]+)"\s+lon="([^"]+)"[^>]*>.*?(?:<ele>([^<]+)</ele>)?.*?</trkpt>"#
-            ).unwrap();
-            
-            for cap in trkpt_regex.captures_iter(content) {
-                if let (Some(lat), Some(lon)) = (cap.get(1), cap.get(2)) {
-                    let lat_val: f64 = lat.as_str().parse().unwrap_or(0.0);
-                    let lon_val: f64 = lon.as_str().parse().unwrap_or(0.0);
-                    let ele_val: f64 = cap.get(3)
-                        .map(|e| e.as_str().parse().unwrap_or(0.0))
-                        .unwrap_or(0.0);
-                    
-                    // Validate coordinates
-                    if lat_val.abs() <= 90.0 && lon_val.abs() <= 180.0 {
-                        points.push(GpxPoint {
-                            lat: lat_val,
-                            lon: lon_val,
-                            ele: ele_val,
-                            time: None,
-                        });
-                    }
-                }
+
+            let name_end = match rest[1..].find(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+                Some(end) => end + 1,
+                None => break,
+            };
+            let name = &rest[1..name_end];
+            let tag_end = match rest.find('>') {
+                Some(end) => end + 1,
+                None => break,
+            };
+
+            let local_name = name.rsplit(':').next().unwrap_or(name);
+            if local_name != "trkpt" && local_name != "wpt" {
+                position = start + tag_end;
+                continue;
             }
-            
-            // Try waypoints if no track points found
-            if points.is_empty() {
-                let wpt_regex = regex::Regex::new(
-                    r#"<wpt\s+lat="([^"]+)"\s+lon="([^"]+)"[^>]*>.*?(?:<ele>([^<]+)</ele>)?.*?</wpt>"#
-                ).unwrap();
-                
-                for cap in wpt_regex.captures_iter(content) {
-                    if let (Some(lat), Some(lon)) = (cap.get(1), cap.get(2)) {
-                        let lat_val: f64 = lat.as_str().parse().unwrap_or(0.0);
-                        let lon_val: f64 = lon.as_str().parse().unwrap_or(0.0);
-                        let ele_val: f64 = cap.get(3)
-                            .map(|e| e.as_str().parse().unwrap_or(0.0))
-                            .unwrap_or(0.0);
-                        
-                        if lat_val.abs() <= 90.0 && lon_val.abs() <= 180.0 {
-                            points.push(GpxPoint {
-                                lat: lat_val,
-                                lon: lon_val,
-                                ele: ele_val,
-                                time: None,
-                            });
-                        }
-                    }
+
+            // Self-closing elements carry no elevation
+            let element_end = if rest[..tag_end].ends_with("/>") {
+                tag_end
+            } else {
+                match rest.find(&format!("</{}>", name)) {
+                    Some(close) => close + name.len() + 3,
+                    None => break,
+                }
+            };
+
+            if let Some(point) = parse_point_element(&rest[..tag_end], &rest[tag_end..element_end]) {
+                if local_name == "trkpt" {
+                    self.track_points.push(point);
+                } else {
+                    self.waypoints.push(point);
                 }
             }
+
+            position = start + element_end;
         }
+
+        self.text.drain(..position);
+
+        if self.text.len() > MAX_PENDING_TEXT {
+            return Err(ApiError::BadRequest("Malformed GPX file: element too large".to_string()));
+        }
+
+        Ok(())
     }
-    
-    Ok(points)
 }
 
-/// Strip non-essential data and optimize point density
-fn strip_and_optimize_points(mut points: Vec<GpxPoint>) -> Result<Vec<GpxPoint>, ApiError> {
-    if points.is_empty() {
-        return Ok(points);
+/// Build a point from an element's opening tag and body
+fn parse_point_element(open_tag: &str, body: &str) -> Option<GpxPoint> {
+    let lat: f64 = attribute(open_tag, "lat")?.trim().parse().ok()?;
+    let lon: f64 = attribute(open_tag, "lon")?.trim().parse().ok()?;
+
+    // Validate coordinates
+    if !(lat.abs() <= 90.0 && lon.abs() <= 180.0) {
+        return None;
     }
-    
-    // Step 1: Remove duplicate consecutive points
-    points.dedup_by(|a, b| {
-        (a.lat - b.lat).abs() < 0.000001 && 
-        (a.lon - b.lon).abs() < 0.000001
-    });
-    
-    // Step 2: Limit total number of points
-    if points.len() > MAX_POINTS {
-        println!("Reducing points from {} to {}", points.len(), MAX_POINTS);
-        points = downsample_points(points, MAX_POINTS);
+
+    let ele = child_text(body, "ele")
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or(0.0);
+
+    let time = child_text(body, "time").map(|text| text.trim().to_string());
+
+    Some(GpxPoint {
+        lat,
+        lon,
+        ele,
+        time,
+    })
+}
+
+/// Value of an attribute in an opening tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+
+    while let Some(index) = rest.find(name) {
+        let preceded_by_space = rest[..index].ends_with(char::is_whitespace);
+        let after = rest[index + name.len()..].trim_start();
+
+        if preceded_by_space {
+            if let Some(value) = after.strip_prefix('=') {
+                let value = value.trim_start();
+                let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+                let end = value[1..].find(quote)?;
+                return Some(&value[1..end + 1]);
+            }
+        }
+
+        rest = &rest[index + name.len()..];
     }
-    
-    // Step 3: Remove points that are too close together
-    let mut optimized = vec![points[0].clone()];
-    let mut last_kept = &points[0];
-    
-    for point in points.iter().skip(1) {
-        let distance = haversine_distance(last_kept.lat, last_kept.lon, point.lat, point.lon) * 1000.0;
-        
-        if distance >= MIN_DISTANCE_METERS {
-            optimized.push(point.clone());
-            last_kept = point;
+
+    None
+}
+
+/// Text of the first child element with the given local name, ignoring extensions
+fn child_text<'a>(body: &'a str, local_name: &str) -> Option<&'a str> {
+    let mut rest = body;
+
+    while let Some(offset) = rest.find('<') {
+        rest = &rest[offset + 1..];
+
+        let name_end = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+        let name = &rest[..name_end];
+        let tag_end = rest.find('>')?;
+
+        match name.rsplit(':').next() {
+            Some(local) if local == local_name => {
+                let text = &rest[tag_end + 1..];
+                return Some(&text[..text.find('<')?]);
+            }
+            Some("extensions") => {
+                let close = format!("</{}>", name);
+                rest = &rest[rest.find(&close)? + close.len()..];
+            }
+            _ => rest = &rest[tag_end + 1..],
         }
     }
-    
-    // Always keep the last point
-    if optimized.len() > 1 {
-        let last_point = points.last().unwrap();
-        let last_optimized = optimized.last().unwrap();
-        
-        if last_point.lat != last_optimized.lat || last_point.lon != last_optimized.lon {
-            optimized.push(last_point.clone());
+
+    None
+}
+
+/// Keeps points at least `MIN_DISTANCE_METERS` apart as they stream in, and
+/// every `stride`-th of those so the whole track keeps the same density
+struct PointThinner {
+    points: Vec<GpxPoint>,
+    last_seen: Option<GpxPoint>,
+    /// Last point far enough from its predecessor, stored or not
+    last_accepted: Option<(f64, f64)>,
+    accepted: usize,
+    stride: usize,
+    seen: usize,
+    /// Length through every point seen, in km
+    raw_distance_km: f64,
+    last_raw: Option<(f64, f64)>,
+}
+
+impl Default for PointThinner {
+    fn default() -> Self {
+        Self {
+            points: Vec::new(),
+            last_seen: None,
+            last_accepted: None,
+            accepted: 0,
+            stride: 1,
+            seen: 0,
+            raw_distance_km: 0.0,
+            last_raw: None,
         }
     }
-    
-    // Step 4: Clean elevation data
-    clean_elevation_data(&mut optimized);
-    
-    // Ensure we have at least 2 points
-    if optimized.len() < 2 {
-        return Err(ApiError::BadRequest("Insufficient valid points after optimization".to_string()));
+}
+
+impl PointThinner {
+    fn push(&mut self, point: GpxPoint) {
+        self.seen += 1;
+        if let Some((lat, lon)) = self.last_raw {
+            self.raw_distance_km += haversine_distance(lat, lon, point.lat, point.lon);
+        }
+        self.last_raw = Some((point.lat, point.lon));
+
+        let accept = match self.last_accepted {
+            Some((lat, lon)) => haversine_distance(lat, lon, point.lat, point.lon) * 1000.0 >= MIN_DISTANCE_METERS,
+            None => true,
+        };
+        if !accept {
+            self.last_seen = Some(point);
+            return;
+        }
+
+        self.last_accepted = Some((point.lat, point.lon));
+        self.accepted += 1;
+        if !(self.accepted - 1).is_multiple_of(self.stride) {
+            self.last_seen = Some(point);
+            return;
+        }
+
+        self.points.push(point);
+        self.last_seen = None;
+
+        // Halve the density of what is stored and of what is still to come,
+        // rather than growing without bound
+        if self.points.len() >= MAX_POINTS * 2 {
+            println!("Reducing buffered points from {} to {}", self.points.len(), MAX_POINTS);
+            let newest = self.points.last().cloned();
+            let mut index = 0;
+            self.points.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.stride *= 2;
+            // The buffer was full at an even length, so the newest point went;
+            // keep it as the track's end in case nothing follows it
+            self.last_seen = newest;
+        }
+    }
+
+    fn finish(mut self) -> Result<Vec<GpxPoint>, ApiError> {
+        // Always keep the last point
+        if self.points.len() > 1 {
+            if let Some(last_point) = self.last_seen.take() {
+                self.points.push(last_point);
+            }
+        }
+
+        // Ensure we have at least 2 points
+        if self.points.len() < 2 {
+            return Err(ApiError::BadRequest("Insufficient valid points after optimization".to_string()));
+        }
+
+        Ok(self.points)
     }
-    
-    Ok(optimized)
 }
 
 /// Downsample points to a target count while preserving route shape
-fn downsample_points(points: Vec<GpxPoint>, target_count: usize) -> Vec<GpxPoint> {
+pub(crate) fn downsample_points(points: Vec<GpxPoint>, target_count: usize) -> Vec<GpxPoint> {
     if points.len() <= target_count {
         return points;
     }
@@ -202,10 +388,11 @@ fn downsample_points(points: Vec<GpxPoint>, target_count: usize) -> Vec<GpxPoint
     result
 }
 
-/// Clean elevation data by removing obvious errors
-fn clean_elevation_data(points: &mut Vec<GpxPoint>) {
+/// Clean elevation data by removing obvious errors, returning what was changed
+fn clean_elevation_data(points: &mut Vec<GpxPoint>) -> Vec<TrackCorrection> {
+    let mut corrections = Vec::new();
     if points.is_empty() {
-        return;
+        return corrections;
     }
     
     // Step 1: Replace invalid elevations (0 or unrealistic values)
@@ -216,20 +403,39 @@ fn clean_elevation_data(points: &mut Vec<GpxPoint>) {
     
     if valid_elevations.is_empty() {
         // If no valid elevations, set all to 0
+        corrections.push(TrackCorrection {
+            kind: CorrectionKind::InvalidElevation,
+            lat: points[0].lat,
+            lon: points[0].lon,
+            points: points.len(),
+            magnitude: points[0].ele.abs(),
+        });
         for point in points.iter_mut() {
             point.ele = 0.0;
         }
-        return;
+        return corrections;
     }
     
     let avg_elevation = valid_elevations.iter().sum::<f64>() / valid_elevations.len() as f64;
     
-    // Replace invalid elevations with average
+    // Replace invalid elevations with average, reported as one correction
+    let mut invalid: Option<TrackCorrection> = None;
     for point in points.iter_mut() {
         if point.ele <= -500.0 || point.ele >= 9000.0 {
+            let change = (point.ele - avg_elevation).abs();
+            let correction = invalid.get_or_insert(TrackCorrection {
+                kind: CorrectionKind::InvalidElevation,
+                lat: point.lat,
+                lon: point.lon,
+                points: 0,
+                magnitude: 0.0,
+            });
+            correction.points += 1;
+            correction.magnitude = correction.magnitude.max(change);
             point.ele = avg_elevation;
         }
     }
+    corrections.extend(invalid);
     
     // Step 2: Smooth out extreme elevation spikes
     let mut i = 1;
@@ -247,38 +453,25 @@ fn clean_elevation_data(points: &mut Vec<GpxPoint>) {
         // but neighbors are close to each other, smooth it
         if diff_prev > 100.0 && diff_next > 100.0 && diff_neighbors < 50.0 {
             points[i].ele = (prev_ele + next_ele) / 2.0;
+            corrections.push(TrackCorrection {
+                kind: CorrectionKind::ElevationSpike,
+                lat: points[i].lat,
+                lon: points[i].lon,
+                points: 1,
+                magnitude: (curr_ele - points[i].ele).abs(),
+            });
         }
         
         i += 1;
     }
+    
+    corrections
 }
 
-/// Clean GPX content to handle extended namespaces and formatting issues
-fn clean_gpx_content(content: &str) -> String {
-    let mut cleaned = content.to_string();
-    
-    // Remove BOM if present
-    if cleaned.starts_with('\u{feff}') {
-        cleaned = cleaned.trim_start_matches('\u{feff}').to_string();
-    }
-    
-    // Remove all namespace prefixes from tags
-    let prefixes = vec![
-        "gpxx:", "gpxtrx:", "gpxtpx:", "wptx1:", "trp:", 
-        "adv:", "prs:", "tmd:", "vptm:", "ctx:", "gpxacc:", 
-        "gpxpx:", "vidx1:", "ns2:", "ns3:", "gpxdata:"
-    ];
-    
-    for prefix in prefixes {
-        cleaned = cleaned.replace(&format!("<{}", prefix), "<");
-        cleaned = cleaned.replace(&format!("</{}", prefix), "</");
-    }
-    
-    // Remove extension blocks that might contain problematic data
-    let extension_regex = regex::Regex::new(r"<extensions>.*?</extensions>").unwrap();
-    cleaned = extension_regex.replace_all(&cleaned, "").to_string();
-    
-    cleaned
+fn track_length_km(points: &[GpxPoint]) -> f64 {
+    points.windows(2)
+        .map(|pair| haversine_distance(pair[0].lat, pair[0].lon, pair[1].lat, pair[1].lon))
+        .sum()
 }
 
 fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
@@ -340,10 +533,36 @@ mod tests {
             GpxPoint { lat: 46.001, lon: 7.001, ele: 1010.0, time: None }, // Good
         ];
         
-        let result = strip_and_optimize_points(points).unwrap();
+        let mut thinner = PointThinner::default();
+        for point in points {
+            thinner.push(point);
+        }
+        
+        let result = thinner.finish().unwrap();
         assert_eq!(result.len(), 2); // Should keep first and last good point
     }
     
+    #[test]
+    fn test_long_track_thinned_evenly() {
+        // ~11 m apart, enough to overflow the buffer twice
+        let count = MAX_POINTS * 5;
+        let mut thinner = PointThinner::default();
+        for i in 0..count {
+            thinner.push(GpxPoint { lat: 40.0 + i as f64 * 0.0001, lon: 7.0, ele: 0.0, time: None });
+        }
+
+        let result = thinner.finish().unwrap();
+        assert!(result.len() < MAX_POINTS * 2);
+        let end_lat = 40.0 + (count - 1) as f64 * 0.0001;
+        assert_eq!(result.last().unwrap().lat, end_lat);
+
+        // As many points in the first tenth of the track as in the last
+        let tenth = (end_lat - 40.0) / 10.0;
+        let first = result.iter().filter(|p| p.lat < 40.0 + tenth).count();
+        let last = result.iter().filter(|p| p.lat > end_lat - tenth).count();
+        assert!(first.abs_diff(last) <= 2, "{} vs {}", first, last);
+    }
+
     #[test]
     fn test_elevation_cleaning() {
         let mut points = vec![
@@ -353,10 +572,41 @@ mod tests {
             GpxPoint { lat: 46.3, lon: 7.3, ele: 1100.0, time: None },
         ];
         
-        clean_elevation_data(&mut points);
+        let corrections = clean_elevation_data(&mut points);
         
         // Invalid elevations should be replaced
         assert!(points[1].ele > -500.0);
         assert!(points[2].ele < 9000.0);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].points, 2);
+    }
+    
+    #[test]
+    fn test_stream_parsing_in_chunks() {
+        let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <!-- <trkpt lat="0" lon="0"></trkpt> -->
+  <wpt lat="45.0" lon="6.0"><name>Start</name></wpt>
+  <trk><trkseg>
+    <trkpt lat="46.000" lon="7.000"><ele>1000.0</ele><extensions><gpxtpx:ele>1</gpxtpx:ele></extensions></trkpt>
+    <trkpt lon="7.001" lat="46.001"><time>2024-06-01T08:00:00Z</time><ele>1010.5</ele></trkpt>
+    <trkpt lat="46.002" lon="7.002"/>
+  </trkseg></trk>
+</gpx>"#;
+        
+        let mut parser = GpxStreamParser::new();
+        for chunk in gpx.as_bytes().chunks(13) {
+            parser.push(chunk).unwrap();
+        }
+        let (data, diagnostics) = parser.finish(&TrackRepairOptions::default()).unwrap();
+        
+        assert_eq!(data.points.len(), 3);
+        assert_eq!(data.points[0].ele, 1000.0);
+        assert_eq!(data.points[1].lon, 7.001);
+        assert_eq!(data.points[1].ele, 1010.5);
+        // Timestamps are read for the repair but not stored
+        assert!(diagnostics.has_timestamps);
+        assert!(data.points[1].time.is_none());
+        assert_eq!(diagnostics.raw_points, 3);
     }
 }
@@ -1,9 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::services::effort_model::effort_similarity;
+
 /// Calculate ITRA effort distance based on distance and elevation gain
 /// Formula: effort_distance = distance + (elevation_gain / 100)
 pub fn calculate_itra_effort(distance_km: f64, elevation_gain_m: f64) -> f64 {
     distance_km + (elevation_gain_m / 100.0)
 }
 
+/// UTMB/ITRA distance category, banded by km-effort
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RaceCategory {
+    Xxs,
+    Xs,
+    S,
+    M,
+    L,
+    Xl,
+    Xxl,
+}
+
+impl RaceCategory {
+    /// Lower km-effort bound of each category, hardest first
+    const BANDS: [(f64, RaceCategory); 7] = [
+        (210.0, RaceCategory::Xxl),
+        (155.0, RaceCategory::Xl),
+        (115.0, RaceCategory::L),
+        (75.0, RaceCategory::M),
+        (45.0, RaceCategory::S),
+        (25.0, RaceCategory::Xs),
+        (0.0, RaceCategory::Xxs),
+    ];
+
+    pub fn from_effort(itra_effort_distance: f64) -> Self {
+        Self::BANDS.iter()
+            .find(|(min, _)| itra_effort_distance >= *min)
+            .map(|(_, category)| *category)
+            .unwrap_or(RaceCategory::Xxs)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RaceCategory::Xxs => "XXS",
+            RaceCategory::Xs => "XS",
+            RaceCategory::S => "S",
+            RaceCategory::M => "M",
+            RaceCategory::L => "L",
+            RaceCategory::Xl => "XL",
+            RaceCategory::Xxl => "XXL",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::BANDS.iter()
+            .map(|(_, category)| *category)
+            .find(|category| category.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+
+    /// ITRA endurance level, 0 (XXS) to 6 (XXL)
+    pub fn endurance_level(&self) -> u8 {
+        Self::BANDS.len() as u8 - 1 - Self::BANDS.iter().position(|(_, c)| c == self).unwrap_or(0) as u8
+    }
+}
+
+/// Mountain level from 0 to 12: one level per 10 m of ascent per km.
+/// ITRA doesn't publish its exact formula, so this tracks climbing density only.
+pub fn calculate_mountain_level(distance_km: f64, elevation_gain_m: f64) -> u8 {
+    if distance_km <= 0.0 {
+        return 0;
+    }
+    ((elevation_gain_m / distance_km / 10.0).floor() as i64).clamp(0, 12) as u8
+}
+
 /// Calculate similarity score between two routes based on ITRA effort distance
 pub fn calculate_similarity(
     ref_distance: f64,
@@ -14,12 +83,7 @@ pub fn calculate_similarity(
     let ref_itra = calculate_itra_effort(ref_distance, ref_elevation_gain);
     let cand_itra = calculate_itra_effort(cand_distance, cand_elevation_gain);
     
-    // Calculate percentage difference
-    let diff = (ref_itra - cand_itra).abs() / ref_itra;
-    
-    // Convert to similarity score (1.0 = perfect match, 0.0 = no similarity)
-    // Using exponential decay for smoother scoring
-    (-diff * 5.0).exp()
+    effort_similarity(ref_itra, cand_itra)
 }
 
 #[cfg(test)]
@@ -52,4 +116,19 @@ mod tests {
         let sim3 = calculate_similarity(ref_dist, ref_ele, 10.0, 100.0);
         assert!(sim3 < 0.1);
     }
+    
+    #[test]
+    fn test_race_category() {
+        // UTMB: 171 km with 10,000 m of ascent
+        let effort = calculate_itra_effort(171.0, 10_000.0);
+        let category = RaceCategory::from_effort(effort);
+        assert_eq!(category, RaceCategory::Xxl);
+        assert_eq!(category.endurance_level(), 6);
+        assert_eq!(calculate_mountain_level(171.0, 10_000.0), 5);
+        
+        assert_eq!(RaceCategory::from_effort(44.9), RaceCategory::Xs);
+        assert_eq!(RaceCategory::from_effort(45.0), RaceCategory::S);
+        assert_eq!(RaceCategory::Xxs.endurance_level(), 0);
+        assert_eq!(RaceCategory::parse("xl"), Some(RaceCategory::Xl));
+    }
 }
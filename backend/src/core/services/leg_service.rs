@@ -0,0 +1,114 @@
+use crate::core::algorithms::gradient_analysis::GradientBins;
+use crate::core::algorithms::linear_referencing::{cumulative_distances, slice_track};
+use crate::core::models::aid_station::{AidStation, Leg};
+use crate::core::models::processing::ProcessingOptions;
+use crate::core::models::race::GpxData;
+use crate::core::services::effort_model::EffortModelKind;
+use crate::core::services::elevation_processor::ElevationData;
+use crate::core::services::elevation_service::calculate_gradient_distribution;
+
+/// Aid stations closer than this to the start or finish don't open a new leg
+const MIN_LEG_M: f64 = 50.0;
+
+/// Split the course at each aid station and analyse every leg on its own
+pub fn build_legs(
+    gpx_data: &GpxData,
+    aid_stations: &[AidStation],
+    options: &ProcessingOptions,
+    bins: &GradientBins,
+    effort_model: EffortModelKind,
+) -> Vec<Leg> {
+    let distances = cumulative_distances(gpx_data);
+    let total_m = distances.last().copied().unwrap_or(0.0);
+
+    let mut stops: Vec<(String, f64)> = vec![("Start".to_string(), 0.0)];
+    let mut stations: Vec<&AidStation> = aid_stations.iter().collect();
+    stations.sort_by(|a, b| a.distance_km.total_cmp(&b.distance_km));
+
+    for station in stations {
+        let distance_m = station.distance_km * 1000.0;
+        let last_m = stops.last().map(|(_, d)| *d).unwrap_or(0.0);
+
+        if distance_m - last_m < MIN_LEG_M {
+            // Station at the start (or stacked on the previous one): rename the stop
+            stops.last_mut().unwrap().0 = station.name.clone();
+        } else if total_m - distance_m >= MIN_LEG_M {
+            stops.push((station.name.clone(), distance_m));
+        }
+    }
+
+    let finish_name = aid_stations.iter()
+        .find(|s| total_m - s.distance_km * 1000.0 < MIN_LEG_M)
+        .map(|s| s.name.clone())
+        .unwrap_or_else(|| "Finish".to_string());
+    stops.push((finish_name, total_m));
+
+    let model = effort_model.model();
+
+    stops.windows(2)
+        .enumerate()
+        .map(|(index, pair)| {
+            let (from, start_m) = &pair[0];
+            let (to, end_m) = &pair[1];
+            println!("Leg {}: {} -> {} ({:.2}-{:.2}km)", index + 1, from, to, start_m / 1000.0, end_m / 1000.0);
+
+            let leg_gpx = slice_track(gpx_data, &distances, *start_m, *end_m);
+            let elevation_data = ElevationData::from_gpx_data(&leg_gpx, options);
+
+            Leg {
+                index: index + 1,
+                from: from.clone(),
+                to: to.clone(),
+                start_km: start_m / 1000.0,
+                end_km: end_m / 1000.0,
+                distance_km: (end_m - start_m) / 1000.0,
+                elevation_gain_m: elevation_data.accumulated_ascent.last().copied().unwrap_or(0.0),
+                elevation_loss_m: elevation_data.accumulated_descent.last().copied().unwrap_or(0.0),
+                effort_model,
+                effort_distance_km: model.estimate(&elevation_data).effort_distance_km,
+                gradient_distribution: calculate_gradient_distribution(&leg_gpx, options, bins),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    fn station(name: &str, distance_km: f64) -> AidStation {
+        AidStation {
+            id: name.to_string(),
+            race_id: "race".to_string(),
+            name: name.to_string(),
+            distance_km,
+            lat: 0.0,
+            lon: 0.0,
+            services: vec![],
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn test_legs_between_aid_stations() {
+        // ~5.5 km: 2 km climbing at 5%, then flat
+        let (gpx_data, _) = straight_course(500, |d| d.min(2000.0) * 0.05);
+        let options = ProcessingOptions {
+            smoothed: false,
+            capping: false,
+            ..Default::default()
+        };
+
+        let stations = vec![station("Col", 2.0), station("Start Village", 0.0)];
+        let legs = build_legs(&gpx_data, &stations, &options, &GradientBins::default(), EffortModelKind::Itra);
+
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].from, "Start Village");
+        assert_eq!(legs[0].to, "Col");
+        assert!((legs[0].elevation_gain_m - 100.0).abs() < 1.0);
+        assert!((legs[0].effort_distance_km - 3.0).abs() < 0.05);
+        assert!(legs[1].elevation_gain_m < 1.0);
+        assert_eq!(legs[1].to, "Finish");
+    }
+}
@@ -1,4 +1,15 @@
 pub mod gpx_parser;
+pub mod gpx_encoding;
 pub mod elevation_service;
 pub mod itra_calculator;
+pub mod effort_model;
+pub mod pace_predictor;
+pub mod pacing_plan;
+pub mod solar;
+pub mod daylight_plan;
+pub mod leg_service;
+pub mod cycling_power;
+pub mod sport_profiles;
+pub mod route_editing;
 pub mod elevation_processor;
+pub mod dem_service;
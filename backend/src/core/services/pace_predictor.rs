@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::services::effort_model::minetti_cost;
+use crate::core::services::elevation_processor::ElevationData;
+
+/// Fastest a descent can make the pace relative to the flat; below this the
+/// limit is footing and braking, not energy
+const MIN_PACE_FACTOR: f64 = 0.8;
+
+/// Uncertainty of a prediction from a flat pace alone, as a share of the time
+const FLAT_PACE_SPREAD: f64 = 0.075;
+
+/// Smallest uncertainty claimed when predicting from past activities
+const MIN_ACTIVITY_SPREAD: f64 = 0.03;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictedSplit {
+    /// 1-based kilometre; the last one may be partial
+    pub km: usize,
+    pub distance_km: f64,
+    pub split_seconds: f64,
+    pub elapsed_seconds: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PacePrediction {
+    pub flat_pace_s_per_km: f64,
+    pub splits: Vec<PredictedSplit>,
+    pub finish_seconds: f64,
+    pub finish_low_seconds: f64,
+    pub finish_high_seconds: f64,
+    /// Number of activities the flat pace was derived from, if any
+    pub activities_used: usize,
+}
+
+/// How much slower than the flat a gradient is, from Minetti's cost of running
+pub fn pace_factor(gradient_percent: f64) -> f64 {
+    (minetti_cost(gradient_percent / 100.0) / minetti_cost(0.0)).max(MIN_PACE_FACTOR)
+}
+
+/// Flat-equivalent distance in km of a profile under `pace_factor`
+pub fn grade_adjusted_distance_km(elevation_data: &ElevationData) -> f64 {
+    steps(elevation_data)
+        .map(|(distance_m, gradient)| distance_m * pace_factor(gradient))
+        .sum::<f64>()
+        / 1000.0
+}
+
+/// Running total of flat-equivalent metres at each point of the profile
+pub fn cumulative_adjusted_distance(elevation_data: &ElevationData) -> Vec<f64> {
+    let mut total = 0.0;
+    elevation_data.distance_change.iter()
+        .zip(&elevation_data.gradient_percent)
+        .map(|(distance, gradient)| {
+            total += distance * pace_factor(*gradient);
+            total
+        })
+        .collect()
+}
+
+/// Flat pace implied by each past activity, then their mean and relative spread
+pub fn flat_pace_from_activities(activities: &[(ElevationData, f64)]) -> Option<(f64, f64)> {
+    let paces: Vec<f64> = activities.iter()
+        .filter_map(|(elevation_data, elapsed_seconds)| {
+            let adjusted_km = grade_adjusted_distance_km(elevation_data);
+            (adjusted_km > 0.0 && *elapsed_seconds > 0.0).then(|| elapsed_seconds / adjusted_km)
+        })
+        .collect();
+
+    if paces.is_empty() {
+        return None;
+    }
+
+    let mean = paces.iter().sum::<f64>() / paces.len() as f64;
+    let variance = paces.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / paces.len() as f64;
+
+    Some((mean, (variance.sqrt() / mean).max(MIN_ACTIVITY_SPREAD)))
+}
+
+/// Per-km splits and finish time at `flat_pace_s_per_km`, with the finish
+/// widened by `spread` either side
+pub fn predict(
+    elevation_data: &ElevationData,
+    flat_pace_s_per_km: f64,
+    spread: Option<f64>,
+    activities_used: usize,
+) -> PacePrediction {
+    let mut splits = Vec::new();
+    let mut elapsed = 0.0;
+    let mut split_start = 0.0;
+    let mut distance = 0.0;
+
+    for (distance_m, gradient) in steps(elevation_data) {
+        let seconds_per_m = flat_pace_s_per_km / 1000.0 * pace_factor(gradient);
+        let mut remaining = distance_m;
+
+        // A step may cross one or more km boundaries
+        while distance + remaining >= (splits.len() + 1) as f64 * 1000.0 {
+            let to_boundary = (splits.len() + 1) as f64 * 1000.0 - distance;
+            elapsed += to_boundary * seconds_per_m;
+            distance += to_boundary;
+            remaining -= to_boundary;
+
+            splits.push(PredictedSplit {
+                km: splits.len() + 1,
+                distance_km: distance / 1000.0,
+                split_seconds: elapsed - split_start,
+                elapsed_seconds: elapsed,
+            });
+            split_start = elapsed;
+        }
+
+        elapsed += remaining * seconds_per_m;
+        distance += remaining;
+    }
+
+    if distance - splits.len() as f64 * 1000.0 > 1.0 {
+        splits.push(PredictedSplit {
+            km: splits.len() + 1,
+            distance_km: distance / 1000.0,
+            split_seconds: elapsed - split_start,
+            elapsed_seconds: elapsed,
+        });
+    }
+
+    let spread = spread.unwrap_or(FLAT_PACE_SPREAD);
+
+    PacePrediction {
+        flat_pace_s_per_km,
+        splits,
+        finish_seconds: elapsed,
+        finish_low_seconds: elapsed * (1.0 - spread),
+        finish_high_seconds: elapsed * (1.0 + spread),
+        activities_used,
+    }
+}
+
+fn steps(elevation_data: &ElevationData) -> impl Iterator<Item = (f64, f64)> + '_ {
+    elevation_data.distance_change.iter()
+        .zip(&elevation_data.gradient_percent)
+        .map(|(distance, gradient)| (*distance, *gradient))
+        .filter(|(distance, _)| *distance > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::{straight_course, POINT_SPACING_M};
+
+    fn track(points: usize, grade: f64) -> ElevationData {
+        straight_course(points, |d| d * grade).1
+    }
+
+    #[test]
+    fn test_flat_prediction() {
+        let flat = track(450, 0.0);
+        let distance_km = flat.cumulative_distance.last().unwrap() / 1000.0;
+
+        let prediction = predict(&flat, 300.0, None, 0);
+
+        assert!((prediction.finish_seconds - distance_km * 300.0).abs() < 1.0);
+        assert_eq!(prediction.splits.len(), 5);
+        assert!((prediction.splits[0].split_seconds - 300.0).abs() < 0.5);
+        assert!(prediction.finish_low_seconds < prediction.finish_seconds);
+        assert!(prediction.finish_high_seconds > prediction.finish_seconds);
+    }
+
+    #[test]
+    fn test_activities_calibrate_flat_pace() {
+        // An hour up a 5% hill implies a quicker flat pace than an hour on the flat
+        let hill = track(450, 0.05);
+        let (pace, spread) = flat_pace_from_activities(&[(hill, 3600.0)]).unwrap();
+
+        let distance_km = 449.0 * POINT_SPACING_M / 1000.0;
+        assert!(pace < 3600.0 / distance_km);
+        assert_eq!(spread, MIN_ACTIVITY_SPREAD);
+        assert!(pace_factor(10.0) > 1.5);
+        assert_eq!(pace_factor(-20.0), MIN_PACE_FACTOR);
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::{Duration, NaiveTime, Timelike};
+
+use crate::core::algorithms::resampling::interpolate;
+use crate::core::models::pacing::{Checkpoint, PacingPlan, PlannedCheckpoint};
+use crate::core::services::elevation_processor::ElevationData;
+use crate::core::services::pace_predictor::cumulative_adjusted_distance;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Parse a clock time such as `06:00` or `06:00:30`
+pub fn parse_clock_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(value.trim(), "%H:%M"))
+        .map_err(|_| format!("Invalid clock time: {}", value))
+}
+
+/// Parse a duration such as `10:30` or `26:15:00` into seconds
+pub fn parse_duration(value: &str) -> Result<f64, String> {
+    let parts: Vec<&str> = value.trim().split(':').collect();
+    let numbers: Option<Vec<u32>> = parts.iter().map(|part| part.parse().ok()).collect();
+
+    match numbers.as_deref() {
+        Some([hours, minutes]) if *minutes < 60 => Ok((*hours as f64 * 60.0 + *minutes as f64) * 60.0),
+        Some([hours, minutes, seconds]) if *minutes < 60 && *seconds < 60 => {
+            Ok(*hours as f64 * 3600.0 + *minutes as f64 * 60.0 + *seconds as f64)
+        }
+        _ => Err(format!("Invalid duration: {}", value)),
+    }
+}
+
+/// Spread `target_seconds` over the course in proportion to grade-adjusted
+/// distance, so every stretch takes the same effort, and check each arrival
+/// against its cut-off
+pub fn build_pacing_plan(
+    elevation_data: &ElevationData,
+    checkpoints: &[Checkpoint],
+    start_time: NaiveTime,
+    target_seconds: f64,
+) -> PacingPlan {
+    let distances = &elevation_data.cumulative_distance;
+    let adjusted = cumulative_adjusted_distance(elevation_data);
+    let total_m = distances.last().copied().unwrap_or(0.0);
+    let total_adjusted = adjusted.last().copied().unwrap_or(0.0);
+    let start_seconds = start_time.num_seconds_from_midnight() as f64;
+
+    let elapsed_at = |distance_m: f64| {
+        if total_adjusted <= 0.0 {
+            return 0.0;
+        }
+        target_seconds * interpolate(distances, &adjusted, distance_m) / total_adjusted
+    };
+
+    let mut stops: Vec<(String, f64, Option<String>, i64)> = checkpoints.iter()
+        .map(|c| (c.name.clone(), c.distance_km, c.cutoff_time.clone(), c.cutoff_day))
+        .collect();
+    stops.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    // Always end the plan at the finish line
+    if stops.last().map(|stop| total_m / 1000.0 - stop.1 > 0.05).unwrap_or(true) {
+        stops.push(("Finish".to_string(), total_m / 1000.0, None, 0));
+    }
+
+    let planned: Vec<PlannedCheckpoint> = stops.into_iter()
+        .map(|(name, distance_km, cutoff_time, cutoff_day)| {
+            let elapsed_seconds = elapsed_at(distance_km * 1000.0);
+            let arrival = start_seconds + elapsed_seconds;
+            let arrival_day = (arrival / SECONDS_PER_DAY).floor() as i64;
+
+            // Cut-offs that can't be parsed were rejected when they were saved
+            let cutoff = cutoff_time.as_deref()
+                .and_then(|time| parse_clock_time(time).ok())
+                .map(|time| cutoff_day as f64 * SECONDS_PER_DAY + time.num_seconds_from_midnight() as f64);
+            let margin_seconds = cutoff.map(|cutoff| cutoff - arrival);
+
+            PlannedCheckpoint {
+                name,
+                distance_km,
+                elapsed_seconds,
+                arrival_time: clock_time(start_time, elapsed_seconds),
+                arrival_day,
+                cutoff_day: cutoff_time.as_ref().map(|_| cutoff_day),
+                cutoff_time,
+                margin_seconds,
+                misses_cutoff: margin_seconds.map(|margin| margin < 0.0).unwrap_or(false),
+            }
+        })
+        .collect();
+
+    PacingPlan {
+        start_time: start_time.format("%H:%M:%S").to_string(),
+        target_seconds,
+        missed_cutoffs: planned.iter().filter(|c| c.misses_cutoff).count(),
+        checkpoints: planned,
+    }
+}
+
+fn clock_time(start_time: NaiveTime, elapsed_seconds: f64) -> String {
+    let (time, _) = start_time.overflowing_add_signed(Duration::seconds(elapsed_seconds.round() as i64));
+    time.format("%H:%M:%S").to_string()
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.abs().round() as i64;
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    format!("{}{}:{:02}:{:02}", sign, total / 3600, total / 60 % 60, total % 60)
+}
+
+/// Printable CSV of a pacing plan
+pub fn pacing_plan_to_csv(plan: &PacingPlan) -> String {
+    let mut csv = String::from("checkpoint,distance_km,elapsed,arrival_time,arrival_day,cutoff_time,cutoff_day,margin,status\n");
+
+    for checkpoint in &plan.checkpoints {
+        let status = match (checkpoint.margin_seconds, checkpoint.misses_cutoff) {
+            (None, _) => "",
+            (Some(_), true) => "MISSED",
+            (Some(_), false) => "OK",
+        };
+
+        csv.push_str(&format!(
+            "\"{}\",{:.2},{},{},{},{},{},{},{}\n",
+            checkpoint.name.replace('"', "\"\""),
+            checkpoint.distance_km,
+            format_duration(checkpoint.elapsed_seconds),
+            checkpoint.arrival_time,
+            checkpoint.arrival_day,
+            checkpoint.cutoff_time.as_deref().unwrap_or(""),
+            checkpoint.cutoff_day.map(|day| day.to_string()).unwrap_or_default(),
+            checkpoint.margin_seconds.map(format_duration).unwrap_or_default(),
+            status,
+        ));
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    fn checkpoint(name: &str, distance_km: f64, cutoff_time: &str, cutoff_day: i64) -> Checkpoint {
+        Checkpoint {
+            id: name.to_string(),
+            race_id: "race".to_string(),
+            name: name.to_string(),
+            distance_km,
+            cutoff_time: Some(cutoff_time.to_string()),
+            cutoff_day,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_flags_missed_cutoffs() {
+        // ~10 km flat: at an even 20 hours every km takes about 2 hours
+        let (_, elevation_data) = straight_course(900, |_| 1000.0);
+        let target = parse_duration("20:00").unwrap();
+
+        let checkpoints = vec![
+            checkpoint("Late", 8.0, "09:00", 1),
+            checkpoint("Early", 2.0, "09:00", 0),
+        ];
+        let plan = build_pacing_plan(&elevation_data, &checkpoints, parse_clock_time("06:00").unwrap(), target);
+
+        assert_eq!(plan.checkpoints.len(), 3);
+        assert_eq!(plan.checkpoints[0].name, "Early");
+        // 2 km of ~10 at 20 h is ~4 h: arrives ~10:00, an hour too late
+        assert!(plan.checkpoints[0].misses_cutoff);
+        // 8 km is ~16 h: arrives ~22:00 on day 0, well before 09:00 the next day
+        assert!(!plan.checkpoints[1].misses_cutoff);
+        assert_eq!(plan.checkpoints[1].arrival_day, 0);
+        assert_eq!(plan.checkpoints[2].name, "Finish");
+        assert_eq!(plan.checkpoints[2].arrival_day, 1);
+        assert_eq!(plan.missed_cutoffs, 1);
+
+        let csv = pacing_plan_to_csv(&plan);
+        assert!(csv.lines().nth(1).unwrap().ends_with("MISSED"));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("10:30").unwrap(), 37_800.0);
+        assert_eq!(parse_duration("26:15:10").unwrap(), 94_510.0);
+        assert!(parse_duration("10:75").is_err());
+    }
+}
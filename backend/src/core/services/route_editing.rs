@@ -0,0 +1,115 @@
+use crate::core::algorithms::linear_referencing::{cumulative_distances, slice_track};
+use crate::core::models::race::{GpxData, GpxPoint};
+use crate::core::services::gpx_parser::{downsample_points, MAX_POINTS};
+
+/// Shortest course an edit may leave behind, in metres
+const MIN_COURSE_M: f64 = 10.0;
+
+/// Keep only the part of the course between two distances
+pub fn trim(gpx_data: &GpxData, from_km: f64, to_km: f64) -> Result<GpxData, String> {
+    let distances = cumulative_distances(gpx_data);
+    let total_m = distances.last().copied().unwrap_or(0.0);
+    let (from_m, to_m) = (from_km * 1000.0, to_km * 1000.0);
+
+    if from_m < 0.0 || to_m > total_m + 1.0 {
+        return Err(format!("Trim range must be within 0-{:.2} km", total_m / 1000.0));
+    }
+    if to_m - from_m < MIN_COURSE_M {
+        return Err("Trim range is empty".to_string());
+    }
+
+    Ok(slice_track(gpx_data, &distances, from_m, to_m.min(total_m)))
+}
+
+/// Run the course the other way; timestamps no longer apply and are dropped
+pub fn reverse(gpx_data: &GpxData) -> GpxData {
+    GpxData {
+        points: gpx_data.points.iter()
+            .rev()
+            .map(|point| GpxPoint { time: None, ..point.clone() })
+            .collect(),
+    }
+}
+
+/// Cut the course in two at `at_km`; both halves share the cut point
+pub fn split(gpx_data: &GpxData, at_km: f64) -> Result<(GpxData, GpxData), String> {
+    let distances = cumulative_distances(gpx_data);
+    let total_m = distances.last().copied().unwrap_or(0.0);
+    let at_m = at_km * 1000.0;
+
+    if at_m < MIN_COURSE_M || at_m > total_m - MIN_COURSE_M {
+        return Err(format!("Split point must be inside the course (0-{:.2} km)", total_m / 1000.0));
+    }
+
+    Ok((
+        slice_track(gpx_data, &distances, 0.0, at_m),
+        slice_track(gpx_data, &distances, at_m, total_m),
+    ))
+}
+
+/// Run courses one after another, dropping a repeated point where one ends
+/// exactly where the next starts, and reduce the result to as many points as
+/// an upload may have
+pub fn join(courses: &[GpxData]) -> GpxData {
+    let mut points: Vec<GpxPoint> = Vec::new();
+
+    for course in courses {
+        let mut course_points = course.points.iter().peekable();
+        if let (Some(last), Some(first)) = (points.last(), course_points.peek()) {
+            if last.lat == first.lat && last.lon == first.lon {
+                course_points.next();
+            }
+        }
+        points.extend(course_points.cloned());
+    }
+
+    GpxData { points: downsample_points(points, MAX_POINTS) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::straight_course;
+
+    fn straight_track() -> GpxData {
+        // ~1.1 km northwards, rising 10 m per point
+        GpxData {
+            points: (0..=10)
+                .map(|i| GpxPoint { lat: 46.0 + i as f64 * 0.001, lon: 7.0, ele: i as f64 * 10.0, time: None })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_edits_round_trip() {
+        let track = straight_track();
+        let total_m = *cumulative_distances(&track).last().unwrap();
+
+        let trimmed = trim(&track, 0.2, 0.5).unwrap();
+        let trimmed_m = *cumulative_distances(&trimmed).last().unwrap();
+        assert!((trimmed_m - 300.0).abs() < 0.5);
+        assert!(trim(&track, 0.5, 0.5).is_err());
+        assert!(trim(&track, 0.0, 5.0).is_err());
+
+        let reversed = reverse(&track);
+        assert_eq!(reversed.points[0].ele, 100.0);
+        assert_eq!(reversed.points[10].ele, 0.0);
+
+        let (first, second) = split(&track, 0.4).unwrap();
+        let joined = join(&[first, second]);
+        let joined_m = *cumulative_distances(&joined).last().unwrap();
+        assert!((joined_m - total_m).abs() < 0.01);
+        // Every original point plus the cut point, which appears once
+        assert_eq!(joined.points.len(), track.points.len() + 1);
+        assert!(split(&track, 5.0).is_err());
+    }
+
+    #[test]
+    fn test_join_limited_to_upload_size() {
+        let (course, _) = straight_course(MAX_POINTS, |_| 500.0);
+        let joined = join(&[course.clone(), reverse(&course)]);
+
+        assert!(joined.points.len() <= MAX_POINTS + 1);
+        assert_eq!(joined.points.last().unwrap().lat, 46.0);
+    }
+}
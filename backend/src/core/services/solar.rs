@@ -0,0 +1,103 @@
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+
+/// Sun centre zenith at sunrise and sunset, allowing for refraction and the solar disc
+pub const SUNRISE_ZENITH: f64 = 90.833;
+
+/// Sun centre zenith at the start of civil dawn and end of civil dusk
+pub const CIVIL_TWILIGHT_ZENITH: f64 = 96.0;
+
+/// Declination and equation of time (in minutes) at an instant, after the
+/// NOAA solar calculator
+fn solar_terms(at: DateTime<Utc>) -> (f64, f64) {
+    let julian_day = at.timestamp() as f64 / 86_400.0 + 2_440_587.5;
+    let t = (julian_day - 2_451_545.0) / 36_525.0;
+
+    let mean_longitude = (280.46646 + t * (36000.76983 + t * 0.0003032)).rem_euclid(360.0);
+    let mean_anomaly = 357.52911 + t * (35999.05029 - 0.0001537 * t);
+    let eccentricity = 0.016708634 - t * (0.000042037 + 0.0000001267 * t);
+
+    let m = mean_anomaly.to_radians();
+    let centre = m.sin() * (1.914602 - t * (0.004817 + 0.000014 * t))
+        + (2.0 * m).sin() * (0.019993 - 0.000101 * t)
+        + (3.0 * m).sin() * 0.000289;
+    let omega = (125.04 - 1934.136 * t).to_radians();
+    let apparent_longitude = (mean_longitude + centre - 0.00569 - 0.00478 * omega.sin()).to_radians();
+
+    let mean_obliquity = 23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.00059 - t * 0.001813))) / 60.0) / 60.0;
+    let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+    let declination = (obliquity.sin() * apparent_longitude.sin()).asin();
+
+    let y = (obliquity / 2.0).tan().powi(2);
+    let l0 = mean_longitude.to_radians();
+    let equation_of_time = 4.0 * (y * (2.0 * l0).sin() - 2.0 * eccentricity * m.sin()
+        + 4.0 * eccentricity * y * m.sin() * (2.0 * l0).cos()
+        - 0.5 * y * y * (4.0 * l0).sin()
+        - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+        .to_degrees();
+
+    (declination, equation_of_time)
+}
+
+/// Height of the sun's centre above the horizon in degrees, without refraction
+pub fn solar_elevation(at: DateTime<Utc>, lat: f64, lon: f64) -> f64 {
+    let (declination, equation_of_time) = solar_terms(at);
+    let minutes = at.timestamp().rem_euclid(86_400) as f64 / 60.0;
+    let true_solar_time = (minutes + equation_of_time + 4.0 * lon).rem_euclid(1440.0);
+    let hour_angle = (true_solar_time / 4.0 - 180.0).to_radians();
+
+    let lat = lat.to_radians();
+    let cos_zenith = lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos();
+    90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// When the sun's centre crosses `zenith_deg` on `date` (the day whose solar
+/// noon it is at `lon`), rising or setting. None during polar day or night.
+pub fn sun_crossing(date: NaiveDate, lat: f64, lon: f64, zenith_deg: f64, rising: bool) -> Option<DateTime<Utc>> {
+    let midnight = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?);
+    let mut estimate = midnight + Duration::minutes((720.0 - 4.0 * lon) as i64);
+
+    // The sun's position barely moves in a few hours, so one refinement
+    // from the first estimate is within seconds
+    for _ in 0..2 {
+        let (declination, equation_of_time) = solar_terms(estimate);
+        let lat_r = lat.to_radians();
+        let cos_hour_angle = zenith_deg.to_radians().cos() / (lat_r.cos() * declination.cos())
+            - lat_r.tan() * declination.tan();
+        if !(-1.0..=1.0).contains(&cos_hour_angle) {
+            return None;
+        }
+        let hour_angle = cos_hour_angle.acos().to_degrees();
+
+        let noon_minutes = 720.0 - 4.0 * lon - equation_of_time;
+        let minutes = if rising { noon_minutes - 4.0 * hour_angle } else { noon_minutes + 4.0 * hour_angle };
+        estimate = midnight + Duration::seconds((minutes * 60.0).round() as i64);
+    }
+
+    Some(estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sunrise_and_sunset_at_greenwich() {
+        // Equinox 2024 at Greenwich: sunrise 06:02, sunset 18:13 (UTC)
+        let date = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+        let sunrise = sun_crossing(date, 51.4769, 0.0, SUNRISE_ZENITH, true).unwrap();
+        let sunset = sun_crossing(date, 51.4769, 0.0, SUNRISE_ZENITH, false).unwrap();
+
+        let expected_sunrise = Utc.with_ymd_and_hms(2024, 3, 20, 6, 2, 0).unwrap();
+        let expected_sunset = Utc.with_ymd_and_hms(2024, 3, 20, 18, 13, 0).unwrap();
+        assert!((sunrise - expected_sunrise).num_seconds().abs() < 120, "{}", sunrise);
+        assert!((sunset - expected_sunset).num_seconds().abs() < 120, "{}", sunset);
+
+        let dusk = sun_crossing(date, 51.4769, 0.0, CIVIL_TWILIGHT_ZENITH, false).unwrap();
+        assert!(dusk > sunset);
+        assert!((solar_elevation(dusk, 51.4769, 0.0) + 6.0).abs() < 0.1);
+
+        // No sunset at the North Cape in midsummer
+        let midsummer = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        assert!(sun_crossing(midsummer, 71.17, 25.78, SUNRISE_ZENITH, false).is_none());
+    }
+}
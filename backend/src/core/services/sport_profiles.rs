@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::core::algorithms::gradient_analysis::GradientBins;
+use crate::core::algorithms::smoothing::SmoothingMethod;
+use crate::core::algorithms::terrain_classification::{classify_terrain, TerrainClass};
+use crate::core::models::race::GpxData;
+use crate::core::models::processing::{CappingThreshold, ProcessingOptions, TRAIL_CAPPING};
+use crate::core::models::sport::Sport;
+use crate::core::services::effort_model::EffortModelKind;
+use crate::core::services::elevation_processor::ElevationData;
+
+/// Processing and metric defaults for one sport
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SportProfile {
+    pub sport: Sport,
+    pub smoothing: SmoothingMethod,
+    pub smoothing_max_hilliness: f64,
+    pub capping_thresholds: Vec<CappingThreshold>,
+    pub gradient_window_m: f64,
+    pub gradient_bins: GradientBins,
+    pub effort_model: EffortModelKind,
+}
+
+impl SportProfile {
+    /// Processing options a request starts from before its own overrides
+    pub fn processing_options(&self) -> ProcessingOptions {
+        ProcessingOptions {
+            smoothing: self.smoothing,
+            smoothing_max_hilliness: self.smoothing_max_hilliness,
+            capping_thresholds: self.capping_thresholds.clone(),
+            gradient_window_m: self.gradient_window_m,
+            ..ProcessingOptions::default()
+        }
+    }
+
+    /// Terrain class stored with a race, from this profile's default processing
+    pub fn terrain(&self, gpx_data: &GpxData) -> TerrainClass {
+        let elevation_data = ElevationData::from_gpx_data(gpx_data, &self.processing_options());
+        classify_terrain(&elevation_data).terrain
+    }
+
+    fn builtin(sport: Sport) -> Self {
+        let capping = |table: &[(f64, f64, f64)]| {
+            table.iter()
+                .map(|(limit, up, down)| CappingThreshold::new(*limit, *up, *down))
+                .collect::<Vec<_>>()
+        };
+        let bins = |edges: &[f64]| GradientBins {
+            edges: edges.to_vec(),
+            flat_threshold: 0.0,
+        };
+        let moving_average = |window_m: f64| SmoothingMethod::MovingAverage { window_m, centered: false };
+
+        match sport {
+            Sport::TrailRun => Self {
+                sport,
+                smoothing: moving_average(100.0),
+                smoothing_max_hilliness: 20.0,
+                capping_thresholds: TRAIL_CAPPING.to_vec(),
+                gradient_window_m: 75.0,
+                gradient_bins: GradientBins::default(),
+                effort_model: EffortModelKind::Itra,
+            },
+            Sport::RoadRun => Self {
+                sport,
+                smoothing: moving_average(100.0),
+                smoothing_max_hilliness: 30.0,
+                capping_thresholds: capping(&[(20.0, 10.0, 10.0), (40.0, 15.0, 15.0), (f64::MAX, 20.0, 20.0)]),
+                gradient_window_m: 100.0,
+                gradient_bins: bins(&[0.0, 2.0, 4.0, 6.0, 8.0, 10.0]),
+                effort_model: EffortModelKind::Minetti,
+            },
+            // Paved roads rarely pass 20%, so anything steeper is GPS noise
+            Sport::RoadBike => Self {
+                sport,
+                smoothing: moving_average(150.0),
+                smoothing_max_hilliness: 30.0,
+                capping_thresholds: capping(&[(20.0, 12.0, 12.0), (40.0, 18.0, 18.0), (f64::MAX, 25.0, 25.0)]),
+                gradient_window_m: 200.0,
+                gradient_bins: bins(&[0.0, 3.0, 6.0, 9.0, 12.0, 15.0]),
+                effort_model: EffortModelKind::Itra,
+            },
+            Sport::Gravel => Self {
+                sport,
+                smoothing: moving_average(150.0),
+                smoothing_max_hilliness: 25.0,
+                capping_thresholds: capping(&[(20.0, 15.0, 15.0), (40.0, 20.0, 20.0), (f64::MAX, 25.0, 25.0)]),
+                gradient_window_m: 150.0,
+                gradient_bins: bins(&[0.0, 3.0, 6.0, 9.0, 12.0, 15.0]),
+                effort_model: EffortModelKind::Itra,
+            },
+            Sport::Mtb => Self {
+                sport,
+                smoothing: moving_average(100.0),
+                smoothing_max_hilliness: 20.0,
+                capping_thresholds: capping(&[(20.0, 20.0, 20.0), (40.0, 28.0, 28.0), (f64::MAX, 35.0, 35.0)]),
+                gradient_window_m: 100.0,
+                gradient_bins: bins(&[0.0, 5.0, 10.0, 15.0, 20.0, 25.0]),
+                effort_model: EffortModelKind::Itra,
+            },
+            Sport::Hike => Self {
+                sport,
+                smoothing: moving_average(100.0),
+                smoothing_max_hilliness: 20.0,
+                capping_thresholds: TRAIL_CAPPING.to_vec(),
+                gradient_window_m: 75.0,
+                gradient_bins: GradientBins::default(),
+                effort_model: EffortModelKind::NaismithLangmuir,
+            },
+            // Skin tracks hold steep slopes; 30° (58%) and up is avalanche terrain
+            Sport::SkiTouring => Self {
+                sport,
+                smoothing: moving_average(75.0),
+                smoothing_max_hilliness: 20.0,
+                capping_thresholds: capping(&[(40.0, 35.0, 40.0), (80.0, 45.0, 55.0), (f64::MAX, 60.0, 70.0)]),
+                gradient_window_m: 50.0,
+                gradient_bins: bins(&[0.0, 10.0, 20.0, 30.0, 40.0, 58.0]),
+                effort_model: EffortModelKind::NaismithLangmuir,
+            },
+        }
+    }
+}
+
+/// Profile for every sport: built-in defaults, optionally overridden from a JSON file
+#[derive(Debug, Clone)]
+pub struct SportProfileRegistry {
+    profiles: HashMap<Sport, SportProfile>,
+}
+
+impl Default for SportProfileRegistry {
+    fn default() -> Self {
+        Self {
+            profiles: Sport::ALL.iter().map(|sport| (*sport, SportProfile::builtin(*sport))).collect(),
+        }
+    }
+}
+
+impl SportProfileRegistry {
+    /// Built-in profiles with any listed in `path` (a JSON array of profiles) replacing them
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let overrides: Vec<SportProfile> = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+
+        let mut registry = Self::default();
+        for profile in overrides {
+            if profile.capping_thresholds.is_empty() {
+                return Err(format!("Profile {} has no capping thresholds", profile.sport.as_str()));
+            }
+            registry.profiles.insert(profile.sport, profile);
+        }
+
+        Ok(registry)
+    }
+
+    pub fn get(&self, sport: Sport) -> &SportProfile {
+        // Every sport is filled in by `default`
+        &self.profiles[&sport]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_override_defaults() {
+        let path = std::env::temp_dir().join("volt_sport_profiles_test.json");
+        let mut road = SportProfile::builtin(Sport::RoadBike);
+        road.gradient_window_m = 500.0;
+        std::fs::write(&path, serde_json::to_string(&vec![road]).unwrap()).unwrap();
+
+        let registry = SportProfileRegistry::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(registry.get(Sport::RoadBike).gradient_window_m, 500.0);
+        assert_eq!(registry.get(Sport::Hike).effort_model, EffortModelKind::NaismithLangmuir);
+
+        // Road capping is far stricter than trail capping
+        let road_options = registry.get(Sport::RoadBike).processing_options();
+        let trail_options = registry.get(Sport::TrailRun).processing_options();
+        assert!(road_options.capping_thresholds[0].max_up < trail_options.capping_thresholds[0].max_up);
+    }
+}
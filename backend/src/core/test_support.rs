@@ -0,0 +1,31 @@
+//! Fixtures shared by the algorithm and service tests
+
+use crate::core::models::processing::ProcessingOptions;
+use crate::core::models::race::{GpxData, GpxPoint};
+use crate::core::services::elevation_processor::ElevationData;
+
+/// Distance between consecutive points of a straight course, in metres
+pub const POINT_SPACING_M: f64 = 11.12;
+
+/// A straight northward course of `points` points ~11 m apart, with `ele`
+/// giving the elevation at each distance from the start in metres, and its
+/// elevation data without smoothing or capping
+pub fn straight_course(points: usize, ele: impl Fn(f64) -> f64) -> (GpxData, ElevationData) {
+    let gpx_data = GpxData {
+        points: (0..points)
+            .map(|i| GpxPoint {
+                lat: 46.0 + i as f64 * 0.0001,
+                lon: 7.0,
+                ele: ele(i as f64 * POINT_SPACING_M),
+                time: None,
+            })
+            .collect(),
+    };
+    let options = ProcessingOptions {
+        smoothed: false,
+        capping: false,
+        ..Default::default()
+    };
+    let elevation_data = ElevationData::from_gpx_data(&gpx_data, &options);
+    (gpx_data, elevation_data)
+}
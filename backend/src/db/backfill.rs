@@ -0,0 +1,43 @@
+use sqlx::SqlitePool;
+
+use crate::core::algorithms::route_topology::detect_topology;
+use crate::core::models::race::GpxData;
+use crate::core::models::sport::Sport;
+use crate::core::services::sport_profiles::SportProfileRegistry;
+use crate::errors::handlers::ApiError;
+
+/// Fill in the analysis of races stored before it was recorded on import,
+/// one race at a time so large libraries are never loaded at once
+pub async fn backfill_races(pool: &SqlitePool, sport_profiles: &SportProfileRegistry) -> Result<usize, ApiError> {
+    let ids = sqlx::query!("SELECT id FROM races WHERE terrain IS NULL OR topology IS NULL")
+        .fetch_all(pool)
+        .await?;
+    
+    for row in &ids {
+        let race = sqlx::query!("SELECT gpx_data, sport FROM races WHERE id = ?", row.id)
+            .fetch_one(pool)
+            .await?;
+        let gpx_data: GpxData = serde_json::from_str(&race.gpx_data)?;
+        let profile = sport_profiles.get(Sport::parse(&race.sport).unwrap_or_default()).clone();
+        
+        let (terrain, topology) = tokio::task::spawn_blocking(move || {
+            (profile.terrain(&gpx_data).as_str(), detect_topology(&gpx_data))
+        })
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Race backfill failed: {}", e)))?;
+        let laps = topology.laps as i64;
+        let topology = topology.topology.as_str();
+        
+        sqlx::query!(
+            "UPDATE races SET terrain = ?, topology = ?, laps = ? WHERE id = ?",
+            terrain,
+            topology,
+            laps,
+            row.id
+        )
+        .execute(pool)
+        .await?;
+    }
+    
+    Ok(ids.len())
+}
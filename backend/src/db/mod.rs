@@ -1,3 +1,4 @@
 pub mod models;
 pub mod queries;
 pub mod pool;
+pub mod backfill;
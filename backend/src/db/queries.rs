@@ -80,7 +80,8 @@ pub mod races {
             SELECT 
                 id, user_id, name, gpx_data,
                 distance_km, elevation_gain_m, elevation_loss_m,
-                itra_effort_distance, created_at
+                itra_effort_distance, category, mountain_level,
+                elevation_source, sport, terrain, topology, laps, created_at
             FROM races 
             WHERE user_id = ? 
             ORDER BY created_at DESC
@@ -99,6 +100,13 @@ pub mod races {
             elevation_gain_m: r.elevation_gain_m,
             elevation_loss_m: r.elevation_loss_m,
             itra_effort_distance: r.itra_effort_distance,
+            category: r.category,
+            mountain_level: r.mountain_level,
+            elevation_source: r.elevation_source,
+            sport: r.sport,
+            terrain: r.terrain,
+            topology: r.topology,
+            laps: r.laps,
             created_at: r.created_at,
         }).collect())
     }
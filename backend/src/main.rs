@@ -1,5 +1,6 @@
 use dotenv::dotenv;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tracing_subscriber;
 
 mod api;
@@ -9,8 +10,9 @@ mod spatial;
 mod config;
 mod errors;
 
-use crate::api::server::create_app;
+use crate::api::server::{create_app, load_sport_profiles};
 use crate::config::settings::Settings;
+use crate::db::backfill::backfill_races;
 use crate::db::pool::{create_pool, migrate};
 
 #[tokio::main]
@@ -31,8 +33,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Run migrations
     migrate(&db_pool).await?;
     
+    let sport_profiles = Arc::new(load_sport_profiles(&settings));
+    
+    // Analyse races stored before newer metrics existed, without holding up startup
+    let backfill_pool = db_pool.clone();
+    let backfill_profiles = sport_profiles.clone();
+    tokio::spawn(async move {
+        match backfill_races(&backfill_pool, &backfill_profiles).await {
+            Ok(count) => tracing::info!("Backfilled {} races", count),
+            Err(e) => tracing::warn!("Race backfill failed: {}", e),
+        }
+    });
+    
     // Create app
-    let app = create_app(db_pool, settings.clone());
+    let app = create_app(db_pool, settings.clone(), sport_profiles);
     
     // Start server
     let addr = SocketAddr::from(([127, 0, 0, 1], settings.port));